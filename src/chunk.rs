@@ -1,5 +1,46 @@
+use std::{
+    fmt::Display,
+    io::{Read, Write},
+    path::Path,
+};
+
 use crate::value::Value;
 
+/// Raised when a chunk is malformed in a way the VM/disassembler can't trust
+/// the compiler to have ruled out — always a sign of a truncated or
+/// otherwise corrupt `.loxc` file, since this build's own compiler never
+/// emits an operand pointing past the end of its output or at the wrong
+/// constant kind.
+#[derive(Debug, Clone, Copy)]
+pub enum ChunkError {
+    CodeIndexOutOfBounds(usize),
+    ConstantIndexOutOfBounds(usize),
+    ConstantNotAString(usize),
+}
+
+impl Display for ChunkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChunkError::CodeIndexOutOfBounds(offset) => {
+                write!(f, "chunk code index {} out of bounds", offset)
+            }
+            ChunkError::ConstantIndexOutOfBounds(idx) => {
+                write!(f, "chunk constant index {} out of bounds", idx)
+            }
+            ChunkError::ConstantNotAString(idx) => {
+                write!(f, "chunk constant {} is not a string", idx)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChunkError {}
+
+/// Magic bytes at the start of a `.loxc` file, identifying it as a serialized chunk.
+const MAGIC: &[u8; 4] = b"LOXC";
+/// Bumped whenever the on-disk `Chunk` layout changes in an incompatible way.
+const VERSION: u16 = 1;
+
 #[repr(u8)]
 #[derive(num_enum::IntoPrimitive, num_enum::TryFromPrimitive)]
 pub enum OpCode {
@@ -8,6 +49,8 @@ pub enum OpCode {
     True,
     False,
     Pop,
+    GetLocal,
+    SetLocal,
     GetGlobal,
     DefineGlobal,
     SetGlobal,
@@ -21,14 +64,20 @@ pub enum OpCode {
     Not,
     Negate,
     Print,
+    Jump,
+    JumpIfFalse,
+    Loop,
     Return,
 }
 
-#[derive(Default)]
+#[derive(Default, serde::Serialize, serde::Deserialize)]
 pub struct Chunk {
     pub code: Vec<u8>,
     pub constants: Vec<Value>,
-    pub lines: Vec<usize>,
+    // Run-length encoded as (line, run length in bytes): source lines are
+    // highly repetitive (one line often compiles to many bytes), so storing
+    // one entry per run instead of one per byte saves a lot of memory.
+    lines: Vec<(usize, usize)>,
 }
 
 impl Chunk {
@@ -38,11 +87,82 @@ impl Chunk {
 
     pub fn write<B: Into<u8>>(&mut self, byte: B, line: usize) {
         self.code.push(byte.into());
-        self.lines.push(line);
+
+        match self.lines.last_mut() {
+            Some((last_line, run)) if *last_line == line => *run += 1,
+            _ => self.lines.push((line, 1)),
+        }
+    }
+
+    /// Looks up the source line that produced the byte at `offset`, walking
+    /// the run-length-encoded runs until one covers it.
+    pub fn get_line(&self, offset: usize) -> usize {
+        let mut covered = 0;
+        for &(line, run) in &self.lines {
+            covered += run;
+            if offset < covered {
+                return line;
+            }
+        }
+        unreachable!("offset {} out of bounds for chunk", offset)
     }
 
     pub fn add_constant(&mut self, value: Value) -> usize {
         self.constants.push(value);
         self.constants.len() - 1
     }
+
+    /// Bounds-checked byte read, for callers (the VM, the disassembler)
+    /// that can't trust the chunk came from this build's own compiler.
+    pub fn read(&self, offset: usize) -> Result<u8, ChunkError> {
+        self.code
+            .get(offset)
+            .copied()
+            .ok_or(ChunkError::CodeIndexOutOfBounds(offset))
+    }
+
+    /// Bounds-checked constant-pool read; see `read`.
+    pub fn read_constant(&self, idx: usize) -> Result<&Value, ChunkError> {
+        self.constants
+            .get(idx)
+            .ok_or(ChunkError::ConstantIndexOutOfBounds(idx))
+    }
+
+    /// Writes this chunk to `path` as a versioned `.loxc` artifact so it can be
+    /// reloaded with `Chunk::load` without re-running the scanner/compiler.
+    /// String constants serialize as their resolved text rather than their
+    /// `InternedStr` handle (see `Obj`'s `Serialize` impl) precisely so this
+    /// round-trips correctly across processes, which don't share an interner.
+    pub fn save(&self, path: &Path) -> eyre::Result<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&VERSION.to_le_bytes())?;
+        bincode::serialize_into(&mut file, self)?;
+        Ok(())
+    }
+
+    /// Loads a chunk previously written by `Chunk::save`, rejecting files with a
+    /// missing magic header or a version this build doesn't understand.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let mut file = std::io::BufReader::new(std::fs::File::open(path)?);
+
+        let mut magic = [0u8; 4];
+        file.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            eyre::bail!("not a compiled lox chunk (bad magic bytes)");
+        }
+
+        let mut version = [0u8; 2];
+        file.read_exact(&mut version)?;
+        let version = u16::from_le_bytes(version);
+        if version != VERSION {
+            eyre::bail!(
+                "unsupported .loxc version {} (this build writes version {})",
+                version,
+                VERSION
+            );
+        }
+
+        Ok(bincode::deserialize_from(file)?)
+    }
 }
@@ -0,0 +1,5 @@
+//! Compile-time debug switches for the bytecode pipeline, mirroring clox's
+//! `common.h` flags. Flip these on locally when debugging the compiler/VM;
+//! they're `false` in normal builds so the extra tracing doesn't spam stdout.
+pub const DEBUG_PRINT_CODE: bool = false;
+pub const DEBUG_TRACE_EXECUTION: bool = false;
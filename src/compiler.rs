@@ -1,12 +1,13 @@
-use std::{convert::TryFrom, io::Write};
+use std::{collections::HashMap, convert::TryFrom, io::Write};
 
 use crate::{
     chunk::Chunk,
     chunk::OpCode,
     common::DEBUG_PRINT_CODE,
     debug::disassemble_chunk,
+    interner::{self, InternedStr},
+    lexer::{Scanner, Span, Token, TokenKind},
     object::Obj,
-    scanner::{Scanner, Token, TokenKind},
     value::Value,
 };
 
@@ -15,6 +16,7 @@ use crate::{
 enum Precedence {
     None = 0,
     Assignment,
+    Conditional,
     Or,
     And,
     Equality,
@@ -91,6 +93,16 @@ fn get_rule<'source, 'chunk>(kind: TokenKind) -> ParseRule<'source, 'chunk> {
             infix: Some(Compiler::binary),
             precedence: Precedence::Factor,
         },
+        TokenKind::Question => ParseRule {
+            prefix: None,
+            infix: Some(Compiler::conditional),
+            precedence: Precedence::Conditional,
+        },
+        TokenKind::Colon => ParseRule {
+            prefix: None,
+            infix: None,
+            precedence: Precedence::None,
+        },
         TokenKind::Bang => ParseRule {
             prefix: Some(Compiler::unary),
             infix: None,
@@ -147,6 +159,11 @@ fn get_rule<'source, 'chunk>(kind: TokenKind) -> ParseRule<'source, 'chunk> {
             precedence: Precedence::None,
         },
         TokenKind::And => ParseRule {
+            prefix: None,
+            infix: Some(Compiler::and_),
+            precedence: Precedence::And,
+        },
+        TokenKind::Break => ParseRule {
             prefix: None,
             infix: None,
             precedence: Precedence::None,
@@ -156,6 +173,11 @@ fn get_rule<'source, 'chunk>(kind: TokenKind) -> ParseRule<'source, 'chunk> {
             infix: None,
             precedence: Precedence::None,
         },
+        TokenKind::Continue => ParseRule {
+            prefix: None,
+            infix: None,
+            precedence: Precedence::None,
+        },
         TokenKind::Else => ParseRule {
             prefix: None,
             infix: None,
@@ -188,8 +210,8 @@ fn get_rule<'source, 'chunk>(kind: TokenKind) -> ParseRule<'source, 'chunk> {
         },
         TokenKind::Or => ParseRule {
             prefix: None,
-            infix: None,
-            precedence: Precedence::None,
+            infix: Some(Compiler::or_),
+            precedence: Precedence::Or,
         },
         TokenKind::Print => ParseRule {
             prefix: None,
@@ -254,12 +276,12 @@ impl<'source, 'chunk> Parser<'source, 'chunk> {
             current: Token {
                 kind: TokenKind::Eof,
                 lexeme: vec![],
-                line: 0,
+                span: Span::default(),
             },
             previous: Token {
                 kind: TokenKind::Eof,
                 lexeme: vec![],
-                line: 0,
+                span: Span::default(),
             },
             had_error: false,
             panic_mode: false,
@@ -343,7 +365,9 @@ impl<'source, 'chunk> Compiler<'source, 'chunk> {
                 | TokenKind::If
                 | TokenKind::While
                 | TokenKind::Print
-                | TokenKind::Return => {
+                | TokenKind::Return
+                | TokenKind::Break
+                | TokenKind::Continue => {
                     return Ok(());
                 }
                 _ => {}
@@ -371,6 +395,16 @@ impl<'source, 'chunk> Compiler<'source, 'chunk> {
     fn statement(&mut self) -> eyre::Result<()> {
         if self.matches(TokenKind::Print)? {
             self.print_statement()?;
+        } else if self.matches(TokenKind::If)? {
+            self.if_statement()?;
+        } else if self.matches(TokenKind::While)? {
+            self.while_statement()?;
+        } else if self.matches(TokenKind::For)? {
+            self.for_statement()?;
+        } else if self.matches(TokenKind::Break)? {
+            self.break_statement()?;
+        } else if self.matches(TokenKind::Continue)? {
+            self.continue_statement()?;
         } else if self.matches(TokenKind::LeftBrace)? {
             self.begin_scope();
             self.block()?;
@@ -381,6 +415,153 @@ impl<'source, 'chunk> Compiler<'source, 'chunk> {
         Ok(())
     }
 
+    /// Compiles `if`/`else` using `JumpIfFalse`/`Jump`: the condition's value
+    /// stays on the stack until each branch pops it, so each branch starts
+    /// with a bare `Pop` to discard it before the branch runs. (The jump
+    /// opcodes and this method's body were already in place by the time this
+    /// doc comment was added — see the `while`/`for`/`break`/`continue` work
+    /// that came right after for where they get reused.)
+    fn if_statement(&mut self) -> eyre::Result<()> {
+        self.consume(TokenKind::LeftParen, b"Expect '(' after 'if'.")?;
+        self.expression()?;
+        self.consume(TokenKind::RightParen, b"Expect ')' after condition.")?;
+
+        let then_jump = self.emit_jump(OpCode::JumpIfFalse);
+        self.emit_byte(OpCode::Pop);
+        self.statement()?;
+
+        let else_jump = self.emit_jump(OpCode::Jump);
+
+        self.patch_jump(then_jump)?;
+        self.emit_byte(OpCode::Pop);
+
+        if self.matches(TokenKind::Else)? {
+            self.statement()?;
+        }
+        self.patch_jump(else_jump)?;
+        Ok(())
+    }
+
+    fn while_statement(&mut self) -> eyre::Result<()> {
+        let loop_start = self.parser.current_chunk.code.len();
+        self.loops.push(LoopContext {
+            continue_target: loop_start,
+            break_jumps: vec![],
+        });
+
+        self.consume(TokenKind::LeftParen, b"Expect '(' after 'while'.")?;
+        self.expression()?;
+        self.consume(TokenKind::RightParen, b"Expect ')' after condition.")?;
+
+        let exit_jump = self.emit_jump(OpCode::JumpIfFalse);
+        self.emit_byte(OpCode::Pop);
+        self.statement()?;
+        self.emit_loop(loop_start)?;
+
+        self.patch_jump(exit_jump)?;
+        self.emit_byte(OpCode::Pop);
+
+        self.patch_breaks()?;
+        Ok(())
+    }
+
+    /// Desugars `for (init; cond; incr) body` into a `while`-shaped loop: the
+    /// increment is compiled once, right after the condition, but reached by
+    /// jumping over it into the body first and looping back to it afterward
+    /// (`body_jump`/`loop_start = increment_start`), so it still only runs
+    /// once per iteration, after the body and before the condition recheck.
+    fn for_statement(&mut self) -> eyre::Result<()> {
+        self.begin_scope();
+        self.consume(TokenKind::LeftParen, b"Expect '(' after 'for'.")?;
+
+        if self.matches(TokenKind::Semicolon)? {
+            // No initializer.
+        } else if self.matches(TokenKind::Var)? {
+            self.var_declaration()?;
+        } else {
+            self.expression_statement()?;
+        }
+
+        let mut loop_start = self.parser.current_chunk.code.len();
+
+        let mut exit_jump = None;
+        if !self.matches(TokenKind::Semicolon)? {
+            self.expression()?;
+            self.consume(TokenKind::Semicolon, b"Expect ';' after loop condition.")?;
+
+            exit_jump = Some(self.emit_jump(OpCode::JumpIfFalse));
+            self.emit_byte(OpCode::Pop);
+        }
+
+        let mut continue_target = loop_start;
+        if !self.matches(TokenKind::RightParen)? {
+            let body_jump = self.emit_jump(OpCode::Jump);
+
+            let increment_start = self.parser.current_chunk.code.len();
+            continue_target = increment_start;
+            self.expression()?;
+            self.emit_byte(OpCode::Pop);
+            self.consume(TokenKind::RightParen, b"Expect ')' after for clauses.")?;
+
+            self.emit_loop(loop_start)?;
+            loop_start = increment_start;
+            self.patch_jump(body_jump)?;
+        }
+
+        self.loops.push(LoopContext {
+            continue_target,
+            break_jumps: vec![],
+        });
+
+        self.statement()?;
+        self.emit_loop(loop_start)?;
+
+        if let Some(exit_jump) = exit_jump {
+            self.patch_jump(exit_jump)?;
+            self.emit_byte(OpCode::Pop);
+        }
+
+        self.patch_breaks()?;
+        self.end_scope();
+        Ok(())
+    }
+
+    fn break_statement(&mut self) -> eyre::Result<()> {
+        self.consume(TokenKind::Semicolon, b"Expect ';' after 'break'.")?;
+
+        if self.loops.is_empty() {
+            self.error(b"Can't use 'break' outside of a loop.")?;
+            return Ok(());
+        }
+
+        let jump = self.emit_jump(OpCode::Jump);
+        self.loops.last_mut().unwrap().break_jumps.push(jump);
+        Ok(())
+    }
+
+    fn continue_statement(&mut self) -> eyre::Result<()> {
+        self.consume(TokenKind::Semicolon, b"Expect ';' after 'continue'.")?;
+
+        let continue_target = match self.loops.last() {
+            None => {
+                self.error(b"Can't use 'continue' outside of a loop.")?;
+                return Ok(());
+            }
+            Some(loop_context) => loop_context.continue_target,
+        };
+        self.emit_loop(continue_target)
+    }
+
+    /// Patches every `break` jump recorded for the innermost loop to land
+    /// here, i.e. just past the loop's exit, then pops that loop's context.
+    fn patch_breaks(&mut self) -> eyre::Result<()> {
+        let loop_context = self.loops.pop().unwrap();
+        for break_jump in loop_context.break_jumps {
+            self.patch_jump(break_jump)?;
+        }
+        Ok(())
+    }
+
     fn consume(&mut self, expected: TokenKind, message: &[u8]) -> eyre::Result<()> {
         if self.parser.current.kind == expected {
             self.advance()?;
@@ -419,7 +600,7 @@ impl<'source, 'chunk> Compiler<'source, 'chunk> {
             return Ok(());
         }
         self.parser.panic_mode = true;
-        eprint!("[line {}] Error", token.line);
+        eprint!("[line {}] Error", token.span.line);
         if token.kind == TokenKind::Eof {
             eprint!(" at end");
         } else if token.kind != TokenKind::Error {
@@ -432,14 +613,44 @@ impl<'source, 'chunk> Compiler<'source, 'chunk> {
         std::io::stderr().write_all(message)?;
         eprintln!();
 
+        if token.kind != TokenKind::Eof {
+            self.print_span_excerpt(token.span)?;
+        }
+
         self.parser.had_error = true;
         Ok(())
     }
 
+    /// Renders the source line containing `span` followed by a caret line
+    /// underlining the exact offending range, using the source bytes the
+    /// scanner was constructed from.
+    fn print_span_excerpt(&self, span: Span) -> eyre::Result<()> {
+        let source = self.parser.scanner.source();
+
+        let line_start = source[..span.start]
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+        let line_end = source[span.end..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(source.len(), |i| span.end + i);
+
+        let mut stderr = std::io::stderr();
+        stderr.write_all(&source[line_start..line_end])?;
+        eprintln!();
+        eprintln!(
+            "{}{}",
+            " ".repeat(span.start - line_start),
+            "^".repeat((span.end - span.start).max(1))
+        );
+        Ok(())
+    }
+
     fn emit_byte<B: Into<u8>>(&mut self, byte: B) {
         self.parser
             .current_chunk
-            .write(byte, self.parser.previous.line);
+            .write(byte, self.parser.previous.span.line);
     }
 
     fn emit_bytes<B1: Into<u8>, B2: Into<u8>>(&mut self, byte1: B1, byte2: B2) {
@@ -451,14 +662,70 @@ impl<'source, 'chunk> Compiler<'source, 'chunk> {
         self.emit_byte(OpCode::Return);
     }
 
+    /// Emits a jump instruction with a placeholder 16-bit operand and returns
+    /// the offset of that operand so `patch_jump` can backfill it later.
+    fn emit_jump<B: Into<u8>>(&mut self, instruction: B) -> usize {
+        self.emit_byte(instruction);
+        self.emit_byte(0xff);
+        self.emit_byte(0xff);
+        self.parser.current_chunk.code.len() - 2
+    }
+
+    /// Backpatches the jump operand at `offset` to land on the current end of
+    /// the chunk, i.e. just past whatever was compiled since `emit_jump`.
+    fn patch_jump(&mut self, offset: usize) -> eyre::Result<()> {
+        let jump = self.parser.current_chunk.code.len() - offset - 2;
+        if jump > std::u16::MAX as usize {
+            self.error(b"Too much code to jump over.")?;
+            return Ok(());
+        }
+
+        let jump = jump as u16;
+        let bytes = jump.to_be_bytes();
+        self.parser.current_chunk.code[offset] = bytes[0];
+        self.parser.current_chunk.code[offset + 1] = bytes[1];
+        Ok(())
+    }
+
+    /// Emits a `Loop` instruction that jumps backward to `loop_start`, the
+    /// offset recorded just before the loop's condition was compiled.
+    fn emit_loop(&mut self, loop_start: usize) -> eyre::Result<()> {
+        self.emit_byte(OpCode::Loop);
+
+        let offset = self.parser.current_chunk.code.len() - loop_start + 2;
+        if offset > std::u16::MAX as usize {
+            self.error(b"Loop body too large.")?;
+            return Ok(());
+        }
+
+        let offset = offset as u16;
+        let bytes = offset.to_be_bytes();
+        self.emit_byte(bytes[0]);
+        self.emit_byte(bytes[1]);
+        Ok(())
+    }
+
     fn make_constant(&mut self, value: Value) -> eyre::Result<u8> {
-        let constant = self.parser.current_chunk.add_constant(value);
+        if let Value::Obj(obj) = &value {
+            let Obj::String(id) = obj.as_ref();
+            if let Some(&index) = self.interned_strings.get(id) {
+                return Ok(index);
+            }
+        }
+
+        let constant = self.parser.current_chunk.add_constant(value.clone());
         if constant > std::u8::MAX as usize {
             self.error(b"Too many constants in one chunk.")?;
-            Ok(0)
-        } else {
-            Ok(constant as u8)
+            return Ok(0);
         }
+        let index = constant as u8;
+
+        if let Value::Obj(obj) = value {
+            let Obj::String(id) = *obj;
+            self.interned_strings.insert(id, index);
+        }
+
+        Ok(index)
     }
 
     fn emit_constant(&mut self, constant: Value) -> eyre::Result<()> {
@@ -534,24 +801,28 @@ impl<'source, 'chunk> Compiler<'source, 'chunk> {
     }
 
     fn string(&mut self, _can_assign: bool) -> eyre::Result<()> {
-        self.emit_constant(Value::Obj(Box::new(Obj::String(
-            self.parser.previous.lexeme[1..self.parser.previous.lexeme.len() - 1].to_owned(),
-        ))))?;
+        let lexeme = &self.parser.previous.lexeme;
+        let text = String::from_utf8_lossy(&lexeme[1..lexeme.len() - 1]);
+        let id = interner::intern(&text);
+        self.emit_constant(Value::Obj(Box::new(Obj::String(id))))?;
         Ok(())
     }
 
-    fn resolve_local(&self, name: &Token) -> isize {
+    fn resolve_local(&mut self, name: &Token) -> eyre::Result<isize> {
         for i in 0..self.local_count {
             let local = &self.locals[i];
             if name.lexeme == local.name.lexeme {
-                return i as isize;
+                if local.depth == -1 {
+                    self.error(b"Can't read local variable in its own initializer.")?;
+                }
+                return Ok(i as isize);
             }
         }
-        -1
+        Ok(-1)
     }
 
     fn named_variable(&mut self, name: &Token, can_assign: bool) -> eyre::Result<()> {
-        let mut arg = self.resolve_local(name);
+        let mut arg = self.resolve_local(name)?;
         let get_op: OpCode;
         let set_op: OpCode;
         if arg != -1 {
@@ -589,6 +860,59 @@ impl<'source, 'chunk> Compiler<'source, 'chunk> {
         Ok(())
     }
 
+    /// Short-circuits `and`: a falsey left operand is left on the stack and
+    /// the jump skips straight past the right operand, so only a truthy left
+    /// side ever pops it to evaluate the right side in its place. (Like
+    /// `or_` below, this was already implemented by the time this doc
+    /// comment was added.)
+    fn and_(&mut self, _can_assign: bool) -> eyre::Result<()> {
+        let end_jump = self.emit_jump(OpCode::JumpIfFalse);
+
+        self.emit_byte(OpCode::Pop);
+        self.parse_precedence(Precedence::And)?;
+
+        self.patch_jump(end_jump)?;
+        Ok(())
+    }
+
+    /// Short-circuits `or`: a truthy left operand jumps past the right
+    /// operand entirely, leaving its own value on the stack; a falsey left
+    /// operand is popped and replaced with the right operand's value.
+    fn or_(&mut self, _can_assign: bool) -> eyre::Result<()> {
+        let else_jump = self.emit_jump(OpCode::JumpIfFalse);
+        let end_jump = self.emit_jump(OpCode::Jump);
+
+        self.patch_jump(else_jump)?;
+        self.emit_byte(OpCode::Pop);
+
+        self.parse_precedence(Precedence::Or)?;
+        self.patch_jump(end_jump)?;
+        Ok(())
+    }
+
+    /// Compiles `cond ? then : else`, reusing the same jump-and-pop shape as
+    /// `if_statement` but at the expression level: whichever arm runs leaves
+    /// its value on the stack in place of the (already-popped) condition.
+    /// The else arm parses at its own precedence so `a ? b : c ? d : e`
+    /// chains right-associatively.
+    fn conditional(&mut self, _can_assign: bool) -> eyre::Result<()> {
+        let then_jump = self.emit_jump(OpCode::JumpIfFalse);
+        self.emit_byte(OpCode::Pop);
+
+        self.expression()?;
+
+        let else_jump = self.emit_jump(OpCode::Jump);
+
+        self.patch_jump(then_jump)?;
+        self.emit_byte(OpCode::Pop);
+
+        self.consume(TokenKind::Colon, b"Expect ':' after then branch of conditional expression.")?;
+        self.parse_precedence(Precedence::Conditional)?;
+
+        self.patch_jump(else_jump)?;
+        Ok(())
+    }
+
     fn parse_precedence(&mut self, precedence: Precedence) -> eyre::Result<()> {
         self.advance()?;
         let prefix_rule = get_rule(self.parser.previous.kind).prefix;
@@ -614,7 +938,9 @@ impl<'source, 'chunk> Compiler<'source, 'chunk> {
     }
 
     fn identifier_constant(&mut self, name: &Token) -> eyre::Result<u8> {
-        self.make_constant(Value::Obj(Box::new(Obj::String(name.lexeme.clone()))))
+        let text = String::from_utf8_lossy(&name.lexeme);
+        let id = interner::intern(&text);
+        self.make_constant(Value::Obj(Box::new(Obj::String(id))))
     }
 
     fn add_local(&mut self, name: Token) -> eyre::Result<()> {
@@ -623,15 +949,22 @@ impl<'source, 'chunk> Compiler<'source, 'chunk> {
             return Ok(());
         }
 
-        let local = Local {
-            name,
-            depth: self.scope_depth as isize,
-        };
+        let local = Local { name, depth: -1 };
         self.locals.push(local);
         self.local_count += 1;
         Ok(())
     }
 
+    /// Marks the most recently declared local as usable by patching in its real
+    /// scope depth, which was left at the sentinel `-1` by `add_local` so that
+    /// `resolve_local` could reject the variable referring to itself mid-initializer.
+    fn mark_initialized(&mut self) {
+        if self.scope_depth == 0 {
+            return;
+        }
+        self.locals[self.local_count - 1].depth = self.scope_depth as isize;
+    }
+
     fn declare_variable(&mut self) -> eyre::Result<()> {
         if self.scope_depth == 0 {
             return Ok(());
@@ -665,6 +998,7 @@ impl<'source, 'chunk> Compiler<'source, 'chunk> {
 
     fn define_variable(&mut self, global: u8) {
         if self.scope_depth > 0 {
+            self.mark_initialized();
             return;
         }
 
@@ -677,6 +1011,13 @@ pub struct Compiler<'source, 'chunk> {
     local_count: usize,
     scope_depth: usize,
     parser: Parser<'source, 'chunk>,
+    // Maps an interned string's handle to the constant index it was first
+    // assigned, so `identifier_constant`/`string` collapse repeated
+    // occurrences onto one constant-pool slot.
+    interned_strings: HashMap<InternedStr, u8>,
+    // Innermost-last stack of enclosing loops, so `break`/`continue` inside
+    // nested loops always resolve against the nearest one.
+    loops: Vec<LoopContext>,
 }
 
 impl<'source, 'chunk> Compiler<'source, 'chunk> {
@@ -686,6 +1027,8 @@ impl<'source, 'chunk> Compiler<'source, 'chunk> {
             local_count: 0,
             scope_depth: 0,
             parser,
+            interned_strings: HashMap::new(),
+            loops: vec![],
         }
     }
 }
@@ -696,6 +1039,16 @@ struct Local {
     depth: isize,
 }
 
+/// Tracks what a `break`/`continue` inside the loop currently being compiled
+/// needs: `continue_target` is where `continue` loops back to (the
+/// condition for a `while`, or the increment clause for a `for`), and
+/// `break_jumps` collects the offsets of `break`'s placeholder `Jump`
+/// operands so they can all be patched to the loop's exit once it's known.
+struct LoopContext {
+    continue_target: usize,
+    break_jumps: Vec<usize>,
+}
+
 pub fn compile(source: &[u8], chunk: &mut crate::chunk::Chunk) -> eyre::Result<bool> {
     let scanner = Scanner::new(source);
     let parser = Parser::new(scanner, chunk);
@@ -709,3 +1062,48 @@ pub fn compile(source: &[u8], chunk: &mut crate::chunk::Chunk) -> eyre::Result<b
     compiler.end_compiler();
     Ok(!compiler.parser.had_error)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+
+    fn compiles(source: &str) -> (bool, Chunk) {
+        let mut chunk = Chunk::new();
+        let ok = compile(source.as_bytes(), &mut chunk).unwrap();
+        (ok, chunk)
+    }
+
+    fn name_constant_count(chunk: &Chunk, name: &str) -> usize {
+        chunk
+            .constants
+            .iter()
+            .filter(|value| match value {
+                Value::Obj(obj) => {
+                    let Obj::String(id) = obj.as_ref();
+                    interner::lookup(*id) == name
+                }
+                _ => false,
+            })
+            .count()
+    }
+
+    #[test]
+    fn repeated_identifiers_share_one_constant_slot() {
+        let (ok, chunk) = compiles("var a = 1; a = 2; print a;");
+        assert!(ok);
+        assert_eq!(name_constant_count(&chunk, "a"), 1);
+    }
+
+    #[test]
+    fn reading_a_local_in_its_own_initializer_is_an_error() {
+        let (ok, _chunk) = compiles("{ var a = a; }");
+        assert!(!ok);
+    }
+
+    #[test]
+    fn using_an_already_defined_local_in_a_new_ones_initializer_is_ok() {
+        let (ok, _chunk) = compiles("{ var a = 1; var b = a; }");
+        assert!(ok);
+    }
+}
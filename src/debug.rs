@@ -13,13 +13,23 @@ pub fn disassemble_chunk(chunk: &Chunk, name: &str) {
 
 pub fn disassemble_instruction(chunk: &Chunk, offset: usize) -> usize {
     print!("{:04} ", offset);
-    if offset > 0 && chunk.lines[offset] == chunk.lines[offset - 1] {
+    if offset >= chunk.code.len()
+        || (offset > 0 && chunk.get_line(offset) == chunk.get_line(offset - 1))
+    {
         print!("   | ");
     } else {
-        print!("{:4} ", chunk.lines[offset]);
+        print!("{:4} ", chunk.get_line(offset));
     }
 
-    match OpCode::try_from(chunk.code[offset]) {
+    let byte = match chunk.read(offset) {
+        Ok(byte) => byte,
+        Err(_) => {
+            println!("<truncated>");
+            return offset + 1;
+        }
+    };
+
+    match OpCode::try_from(byte) {
         Ok(c) => match c {
             OpCode::Constant => constant_instruction("Constant", chunk, offset),
             OpCode::Nil => simple_instruction("Nil", offset),
@@ -41,27 +51,35 @@ pub fn disassemble_instruction(chunk: &Chunk, offset: usize) -> usize {
             OpCode::Not => simple_instruction("Not", offset),
             OpCode::Negate => simple_instruction("Negate", offset),
             OpCode::Print => simple_instruction("Print", offset),
+            OpCode::Jump => jump_instruction("Jump", 1, chunk, offset),
+            OpCode::JumpIfFalse => jump_instruction("JumpIfFalse", 1, chunk, offset),
+            OpCode::Loop => jump_instruction("Loop", -1, chunk, offset),
             OpCode::Return => simple_instruction("Return", offset),
         },
         Err(_) => {
-            println!("Unknown opcode {}", chunk.code[offset]);
+            println!("Unknown opcode {}", byte);
             offset + 1
         }
     }
 }
 
 fn byte_instruction(name: &str, chunk: &Chunk, offset: usize) -> usize {
-    let slot = chunk.code[offset + 1];
-    println!("{:<16} {:4}", name, slot);
+    match chunk.read(offset + 1) {
+        Ok(slot) => println!("{:<16} {:4}", name, slot),
+        Err(_) => println!("{:<16} <truncated>", name),
+    }
     offset + 2
 }
 
 fn constant_instruction(name: &str, chunk: &Chunk, offset: usize) -> usize {
-    let constant = chunk.code[offset + 1];
-    println!(
-        "{:<16} {:4} '{}'",
-        name, constant, chunk.constants[constant as usize]
-    );
+    match chunk.read(offset + 1).and_then(|idx| {
+        chunk
+            .read_constant(idx as usize)
+            .map(|value| (idx, value))
+    }) {
+        Ok((constant, value)) => println!("{:<16} {:4} '{}'", name, constant, value),
+        Err(_) => println!("{:<16} <truncated>", name),
+    }
     offset + 2
 }
 
@@ -69,3 +87,19 @@ fn simple_instruction(name: &str, offset: usize) -> usize {
     println!("{}", name);
     offset + 1
 }
+
+fn jump_instruction(name: &str, sign: isize, chunk: &Chunk, offset: usize) -> usize {
+    match (chunk.read(offset + 1), chunk.read(offset + 2)) {
+        (Ok(hi), Ok(lo)) => {
+            let jump = u16::from_be_bytes([hi, lo]);
+            println!(
+                "{:<16} {:4} -> {}",
+                name,
+                offset,
+                offset as isize + 3 + sign * jump as isize
+            );
+        }
+        _ => println!("{:<16} <truncated>", name),
+    }
+    offset + 3
+}
@@ -1,6 +1,10 @@
 use std::{collections::HashMap, sync::Arc, sync::RwLock};
 
-use crate::{object::Object, runtime_error::RuntimeError, token::Token};
+use crate::{
+    object::Object,
+    runtime_error::{ErrorKind, RuntimeError},
+    token::Token,
+};
 
 pub struct Environment {
     enclosing: Option<Arc<RwLock<Environment>>>,
@@ -38,7 +42,7 @@ impl Environment {
         self.try_get(name).ok_or_else(|| {
             RuntimeError::new(
                 name.clone(),
-                format!("Undefined variable '{}'.", name.lexeme),
+                ErrorKind::UndefinedVariable(name.lexeme.clone()),
             )
         })
     }
@@ -58,8 +62,54 @@ impl Environment {
         self.try_assign(name, value).ok_or_else(|| {
             RuntimeError::new(
                 name.clone(),
-                format!("Undefined variable '{}'.", name.lexeme),
+                ErrorKind::UndefinedVariable(name.lexeme.clone()),
             )
         })
     }
+
+    fn ancestor(env: &Arc<RwLock<Environment>>, distance: usize) -> Arc<RwLock<Environment>> {
+        let mut environment = env.clone();
+        for _ in 0..distance {
+            let enclosing = environment.read().unwrap().enclosing.clone().unwrap();
+            environment = enclosing;
+        }
+        environment
+    }
+
+    /// Looks up `name` at the exact scope `distance` hops up from `env`, as
+    /// resolved by the `Resolver`, instead of walking the enclosing chain.
+    pub fn get_at(
+        env: &Arc<RwLock<Environment>>,
+        distance: usize,
+        name: &Token,
+    ) -> Result<Arc<Object>, RuntimeError> {
+        Self::ancestor(env, distance)
+            .read()
+            .unwrap()
+            .values
+            .get(&name.lexeme)
+            .cloned()
+            .ok_or_else(|| {
+                RuntimeError::new(
+                    name.clone(),
+                    ErrorKind::UndefinedVariable(name.lexeme.clone()),
+                )
+            })
+    }
+
+    /// Assigns `name` at the exact scope `distance` hops up from `env`; see
+    /// `get_at`.
+    pub fn assign_at(
+        env: &Arc<RwLock<Environment>>,
+        distance: usize,
+        name: &Token,
+        value: Arc<Object>,
+    ) -> Result<(), RuntimeError> {
+        Self::ancestor(env, distance)
+            .write()
+            .unwrap()
+            .values
+            .insert(name.lexeme.clone(), value);
+        Ok(())
+    }
 }
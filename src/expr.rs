@@ -1,3 +1,5 @@
+use std::cell::Cell;
+
 use crate::{object::LoxObject, token::Token};
 
 pub trait Visitor<T> {
@@ -42,6 +44,9 @@ impl Expr {
 pub struct Assign {
     pub name: Token,
     pub value: Box<Expr>,
+    // Filled in by the `Resolver` with the number of scopes between this
+    // assignment and the one that declares `name`; `None` means global.
+    pub depth: Cell<Option<usize>>,
 }
 
 #[derive(Debug, Clone)]
@@ -84,4 +89,6 @@ pub struct Unary {
 #[derive(Debug, Clone)]
 pub struct Variable {
     pub name: Token,
+    // Filled in by the `Resolver`; see `Assign::depth`.
+    pub depth: Cell<Option<usize>>,
 }
@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use std::sync::RwLock;
+
+/// A small integer handle for a string that has been interned via `intern`.
+/// Two handles are equal iff the strings they came from are equal, so
+/// comparing `InternedStr`s (e.g. for the `Equal` opcode, or global-variable
+/// name lookups) is an O(1) integer comparison instead of a string compare.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InternedStr(u32);
+
+/// Deduplicates strings the bytecode compiler/VM encounter (literals,
+/// identifiers) so identical text shares one allocation and compares in O(1).
+#[derive(Default)]
+pub struct Interner {
+    strings: Vec<Box<str>>,
+    lookup: HashMap<Box<str>, u32>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&mut self, s: &str) -> InternedStr {
+        if let Some(&id) = self.lookup.get(s) {
+            return InternedStr(id);
+        }
+
+        let id = self.strings.len() as u32;
+        let boxed: Box<str> = s.into();
+        self.strings.push(boxed.clone());
+        self.lookup.insert(boxed, id);
+        InternedStr(id)
+    }
+
+    pub fn lookup(&self, id: InternedStr) -> &str {
+        &self.strings[id.0 as usize]
+    }
+}
+
+lazy_static! {
+    // Shared by the compiler and the VM so a literal compiled in one REPL
+    // line and one compiled in the next still intern to the same handle.
+    static ref INTERNER: RwLock<Interner> = RwLock::new(Interner::new());
+}
+
+pub fn intern(s: &str) -> InternedStr {
+    INTERNER.write().unwrap().intern(s)
+}
+
+pub fn lookup(id: InternedStr) -> String {
+    INTERNER.read().unwrap().lookup(id).to_owned()
+}
@@ -8,7 +8,7 @@ use crate::{
     expr::{self, Expr},
     object::LoxObject,
     object::Object,
-    runtime_error::RuntimeError,
+    runtime_error::{ErrorKind, RuntimeError, Unwind},
     stmt,
     token::Token,
     token::TokenKind,
@@ -20,7 +20,7 @@ fn check_number_operand(operator: &Token, operand: LoxObject) -> Result<(), Runt
     } else {
         Err(RuntimeError::new(
             operator.clone(),
-            String::from("Operand must be a number."),
+            ErrorKind::TypeError(String::from("Operand must be a number.")),
         ))
     }
 }
@@ -35,7 +35,7 @@ fn check_number_operands(
     } else {
         Err(RuntimeError::new(
             operator.clone(),
-            String::from("Operands must be numbers."),
+            ErrorKind::TypeError(String::from("Operands must be numbers.")),
         ))
     }
 }
@@ -69,11 +69,16 @@ impl Interpreter {
 
     pub fn interpret(&mut self, statements: &[stmt::Stmt]) {
         if let Some(e) = statements.iter().find_map(|s| self.execute(s).err()) {
-            crate::runtime_error(e);
+            match e {
+                Unwind::RuntimeError(e) => crate::runtime_error(e),
+                // The resolver rejects `return`/`break`/`continue` outside a
+                // function or loop, so none of these can reach the top level.
+                Unwind::Return(_) | Unwind::Break | Unwind::Continue => unreachable!(),
+            }
         }
     }
 
-    fn execute(&mut self, stmt: &stmt::Stmt) -> Result<(), RuntimeError> {
+    fn execute(&mut self, stmt: &stmt::Stmt) -> Result<(), Unwind> {
         stmt.accept(self)
     }
 
@@ -81,7 +86,7 @@ impl Interpreter {
         &mut self,
         statements: &[stmt::Stmt],
         environment: Environment,
-    ) -> Result<(), RuntimeError> {
+    ) -> Result<(), Unwind> {
         let previous = self.environment.clone();
 
         self.environment = Arc::new(RwLock::new(environment));
@@ -99,20 +104,29 @@ impl Interpreter {
     fn evaluate(&mut self, expr: &Expr) -> Result<LoxObject, RuntimeError> {
         expr.accept(self)
     }
+
+    /// Evaluates a single expression and hands back its value instead of
+    /// discarding it, bypassing `interpret`'s statement-execution path. Used
+    /// by the REPL to auto-print the result of a bare expression typed at
+    /// the prompt, the way most language REPLs echo results.
+    pub fn interpret_expr(&mut self, expr: &Expr) -> Result<LoxObject, RuntimeError> {
+        self.evaluate(expr)
+    }
 }
 
-impl stmt::Visitor<Result<(), RuntimeError>> for Interpreter {
-    fn visit_expression_stmt(&mut self, stmt: &stmt::Expression) -> Result<(), RuntimeError> {
-        self.evaluate(&stmt.expression).map(|_| ())
+impl stmt::Visitor<Result<(), Unwind>> for Interpreter {
+    fn visit_expression_stmt(&mut self, stmt: &stmt::Expression) -> Result<(), Unwind> {
+        self.evaluate(&stmt.expression).map_err(Unwind::RuntimeError)?;
+        Ok(())
     }
 
-    fn visit_print_stmt(&mut self, stmt: &stmt::Print) -> Result<(), RuntimeError> {
+    fn visit_print_stmt(&mut self, stmt: &stmt::Print) -> Result<(), Unwind> {
         let value = self.evaluate(&stmt.expression)?;
         println!("{}", value.read().unwrap());
         Ok(())
     }
 
-    fn visit_var_stmt(&mut self, stmt: &stmt::Var) -> Result<(), RuntimeError> {
+    fn visit_var_stmt(&mut self, stmt: &stmt::Var) -> Result<(), Unwind> {
         let value = if let Some(initializer) = &stmt.initializer {
             Some(self.evaluate(initializer)?)
         } else {
@@ -125,14 +139,22 @@ impl stmt::Visitor<Result<(), RuntimeError>> for Interpreter {
         Ok(())
     }
 
-    fn visit_block_stmt(&mut self, stmt: &stmt::Block) -> Result<(), RuntimeError> {
+    fn visit_block_stmt(&mut self, stmt: &stmt::Block) -> Result<(), Unwind> {
         self.execute_block(
             &stmt.statements,
             Environment::new_enclosed(self.environment.clone()),
         )
     }
 
-    fn visit_if_stmt(&mut self, stmt: &stmt::If) -> Result<(), RuntimeError> {
+    fn visit_break_stmt(&mut self, _stmt: &stmt::Break) -> Result<(), Unwind> {
+        Err(Unwind::Break)
+    }
+
+    fn visit_continue_stmt(&mut self, _stmt: &stmt::Continue) -> Result<(), Unwind> {
+        Err(Unwind::Continue)
+    }
+
+    fn visit_if_stmt(&mut self, stmt: &stmt::If) -> Result<(), Unwind> {
         if self.evaluate(&stmt.condition)?.read().unwrap().as_bool() {
             self.execute(&stmt.then_branch)?;
         } else if let Some(else_branch) = &stmt.else_branch {
@@ -141,21 +163,36 @@ impl stmt::Visitor<Result<(), RuntimeError>> for Interpreter {
         Ok(())
     }
 
-    fn visit_while_stmt(&mut self, stmt: &stmt::While) -> Result<(), RuntimeError> {
+    fn visit_while_stmt(&mut self, stmt: &stmt::While) -> Result<(), Unwind> {
         while self.evaluate(&stmt.condition)?.read().unwrap().as_bool() {
-            self.execute(&stmt.body)?;
+            match self.execute(&stmt.body) {
+                Ok(()) | Err(Unwind::Continue) => {}
+                Err(Unwind::Break) => break,
+                Err(e) => return Err(e),
+            }
+            if let Some(increment) = &stmt.increment {
+                self.evaluate(increment)?;
+            }
         }
         Ok(())
     }
 
-    fn visit_function_stmt(&mut self, stmt: &stmt::Function) -> Result<(), RuntimeError> {
-        let function = Object::new_function(stmt.clone());
+    fn visit_function_stmt(&mut self, stmt: &stmt::Function) -> Result<(), Unwind> {
+        let function = Object::new_function(stmt.clone(), self.environment.clone());
         self.environment
             .write()
             .unwrap()
             .define(&stmt.name.lexeme, function);
         Ok(())
     }
+
+    fn visit_return_stmt(&mut self, stmt: &stmt::Return) -> Result<(), Unwind> {
+        let value = match &stmt.value {
+            Some(expr) => self.evaluate(expr)?,
+            None => Object::nil(),
+        };
+        Err(Unwind::Return(value))
+    }
 }
 
 impl expr::Visitor<Result<LoxObject, RuntimeError>> for Interpreter {
@@ -195,7 +232,9 @@ impl expr::Visitor<Result<LoxObject, RuntimeError>> for Interpreter {
                 } else {
                     return Err(RuntimeError::new(
                         expr.operator.clone(),
-                        String::from("Operands must be two numbers or two strings."),
+                        ErrorKind::TypeError(String::from(
+                            "Operands must be two numbers or two strings.",
+                        )),
                     ));
                 }
             }
@@ -255,16 +294,25 @@ impl expr::Visitor<Result<LoxObject, RuntimeError>> for Interpreter {
     }
 
     fn visit_variable_expr(&mut self, expr: &expr::Variable) -> Result<LoxObject, RuntimeError> {
-        self.environment.read().unwrap().get(&expr.name)
+        match expr.depth.get() {
+            Some(distance) => Environment::get_at(&self.environment, distance, &expr.name),
+            None => self.globals.read().unwrap().get(&expr.name),
+        }
     }
 
     fn visit_assign_expr(&mut self, expr: &expr::Assign) -> Result<LoxObject, RuntimeError> {
         let value = self.evaluate(&expr.value)?;
 
-        self.environment
-            .write()
-            .unwrap()
-            .assign(&expr.name, value.clone())?;
+        match expr.depth.get() {
+            Some(distance) => {
+                Environment::assign_at(&self.environment, distance, &expr.name, value.clone())?
+            }
+            None => self
+                .globals
+                .write()
+                .unwrap()
+                .assign(&expr.name, value.clone())?,
+        }
         Ok(value)
     }
 
@@ -299,18 +347,17 @@ impl expr::Visitor<Result<LoxObject, RuntimeError>> for Interpreter {
         if !callee.read().unwrap().is_callable() {
             return Err(RuntimeError::new(
                 expr.paren.clone(),
-                String::from("Can only call functions and classes."),
+                ErrorKind::TypeError(String::from("Can only call functions and classes.")),
             ));
         }
 
         if arguments.len() != callee.read().unwrap().arity() {
             return Err(RuntimeError::new(
                 expr.paren.clone(),
-                format!(
-                    "Expected {} arguments but got {}.",
-                    callee.read().unwrap().arity(),
-                    arguments.len()
-                ),
+                ErrorKind::ArityMismatch {
+                    expected: callee.read().unwrap().arity(),
+                    got: arguments.len(),
+                },
             ));
         }
 
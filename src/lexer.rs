@@ -0,0 +1,308 @@
+//! Byte-oriented lexer for the bytecode `compiler`, kept separate from the
+//! tree-walk `scanner`/`token` modules since the two front ends tokenize
+//! different representations (raw source bytes here vs. `char`s there) and
+//! report errors through different mechanisms (`TokenKind::Error` tokens
+//! here vs. accumulated `ScanError`s there).
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TokenKind {
+    LeftParen,
+    RightParen,
+    LeftBrace,
+    RightBrace,
+    Comma,
+    Dot,
+    Minus,
+    Plus,
+    Semicolon,
+    Slash,
+    Star,
+    Question,
+    Colon,
+
+    Bang,
+    BangEqual,
+    Equal,
+    EqualEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+
+    Identifier,
+    String,
+    Number,
+
+    And,
+    Break,
+    Class,
+    Continue,
+    Else,
+    False,
+    For,
+    Fun,
+    If,
+    Nil,
+    Or,
+    Print,
+    Return,
+    Super,
+    This,
+    True,
+    Var,
+    While,
+
+    Error,
+    #[default]
+    Eof,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub lexeme: Vec<u8>,
+    pub span: Span,
+}
+
+pub struct Scanner<'source> {
+    source: &'source [u8],
+    start: usize,
+    current: usize,
+    line: usize,
+}
+
+impl<'source> Scanner<'source> {
+    pub fn new(source: &'source [u8]) -> Self {
+        Self {
+            source,
+            start: 0,
+            current: 0,
+            line: 1,
+        }
+    }
+
+    pub fn source(&self) -> &'source [u8] {
+        self.source
+    }
+
+    pub fn scan_token(&mut self) -> Token {
+        self.skip_whitespace();
+        self.start = self.current;
+
+        if self.is_at_end() {
+            return self.make_token(TokenKind::Eof);
+        }
+
+        let c = self.advance();
+
+        if is_alpha(c) {
+            return self.identifier();
+        }
+        if c.is_ascii_digit() {
+            return self.number();
+        }
+
+        match c {
+            b'(' => self.make_token(TokenKind::LeftParen),
+            b')' => self.make_token(TokenKind::RightParen),
+            b'{' => self.make_token(TokenKind::LeftBrace),
+            b'}' => self.make_token(TokenKind::RightBrace),
+            b';' => self.make_token(TokenKind::Semicolon),
+            b',' => self.make_token(TokenKind::Comma),
+            b'.' => self.make_token(TokenKind::Dot),
+            b'-' => self.make_token(TokenKind::Minus),
+            b'+' => self.make_token(TokenKind::Plus),
+            b'/' => self.make_token(TokenKind::Slash),
+            b'*' => self.make_token(TokenKind::Star),
+            b'?' => self.make_token(TokenKind::Question),
+            b':' => self.make_token(TokenKind::Colon),
+            b'!' => {
+                let kind = if self.matches(b'=') {
+                    TokenKind::BangEqual
+                } else {
+                    TokenKind::Bang
+                };
+                self.make_token(kind)
+            }
+            b'=' => {
+                let kind = if self.matches(b'=') {
+                    TokenKind::EqualEqual
+                } else {
+                    TokenKind::Equal
+                };
+                self.make_token(kind)
+            }
+            b'<' => {
+                let kind = if self.matches(b'=') {
+                    TokenKind::LessEqual
+                } else {
+                    TokenKind::Less
+                };
+                self.make_token(kind)
+            }
+            b'>' => {
+                let kind = if self.matches(b'=') {
+                    TokenKind::GreaterEqual
+                } else {
+                    TokenKind::Greater
+                };
+                self.make_token(kind)
+            }
+            b'"' => self.string(),
+            _ => self.error_token("Unexpected character."),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            match self.peek() {
+                b' ' | b'\r' | b'\t' => {
+                    self.advance();
+                }
+                b'\n' => {
+                    self.line += 1;
+                    self.advance();
+                }
+                b'/' if self.peek_next() == b'/' => {
+                    while self.peek() != b'\n' && !self.is_at_end() {
+                        self.advance();
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn identifier(&mut self) -> Token {
+        while is_alpha(self.peek()) || self.peek().is_ascii_digit() {
+            self.advance();
+        }
+
+        let kind = identifier_kind(&self.source[self.start..self.current]);
+        self.make_token(kind)
+    }
+
+    fn number(&mut self) -> Token {
+        while self.peek().is_ascii_digit() {
+            self.advance();
+        }
+
+        if self.peek() == b'.' && self.peek_next().is_ascii_digit() {
+            self.advance();
+            while self.peek().is_ascii_digit() {
+                self.advance();
+            }
+        }
+
+        self.make_token(TokenKind::Number)
+    }
+
+    fn string(&mut self) -> Token {
+        while self.peek() != b'"' && !self.is_at_end() {
+            if self.peek() == b'\n' {
+                self.line += 1;
+            }
+            self.advance();
+        }
+
+        if self.is_at_end() {
+            return self.error_token("Unterminated string.");
+        }
+
+        self.advance();
+        self.make_token(TokenKind::String)
+    }
+
+    fn matches(&mut self, expected: u8) -> bool {
+        if self.is_at_end() || self.source[self.current] != expected {
+            false
+        } else {
+            self.current += 1;
+            true
+        }
+    }
+
+    fn advance(&mut self) -> u8 {
+        self.current += 1;
+        self.source[self.current - 1]
+    }
+
+    fn peek(&self) -> u8 {
+        if self.is_at_end() {
+            b'\0'
+        } else {
+            self.source[self.current]
+        }
+    }
+
+    fn peek_next(&self) -> u8 {
+        if self.current + 1 >= self.source.len() {
+            b'\0'
+        } else {
+            self.source[self.current + 1]
+        }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.current >= self.source.len()
+    }
+
+    fn make_token(&self, kind: TokenKind) -> Token {
+        Token {
+            kind,
+            lexeme: self.source[self.start..self.current].to_vec(),
+            span: Span {
+                start: self.start,
+                end: self.current,
+                line: self.line,
+            },
+        }
+    }
+
+    fn error_token(&self, message: &'static str) -> Token {
+        Token {
+            kind: TokenKind::Error,
+            lexeme: message.as_bytes().to_vec(),
+            span: Span {
+                start: self.start,
+                end: self.current,
+                line: self.line,
+            },
+        }
+    }
+}
+
+fn is_alpha(c: u8) -> bool {
+    c.is_ascii_alphabetic() || c == b'_'
+}
+
+fn identifier_kind(text: &[u8]) -> TokenKind {
+    match text {
+        b"and" => TokenKind::And,
+        b"break" => TokenKind::Break,
+        b"class" => TokenKind::Class,
+        b"continue" => TokenKind::Continue,
+        b"else" => TokenKind::Else,
+        b"false" => TokenKind::False,
+        b"for" => TokenKind::For,
+        b"fun" => TokenKind::Fun,
+        b"if" => TokenKind::If,
+        b"nil" => TokenKind::Nil,
+        b"or" => TokenKind::Or,
+        b"print" => TokenKind::Print,
+        b"return" => TokenKind::Return,
+        b"super" => TokenKind::Super,
+        b"this" => TokenKind::This,
+        b"true" => TokenKind::True,
+        b"var" => TokenKind::Var,
+        b"while" => TokenKind::While,
+        _ => TokenKind::Identifier,
+    }
+}
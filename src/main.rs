@@ -1,23 +1,35 @@
 mod ast_printer;
+mod chunk;
+mod common;
+mod compiler;
+mod debug;
 mod environment;
 mod expr;
+mod interner;
 mod interpreter;
+mod lexer;
 mod object;
+mod optimizer;
 mod parser;
+mod resolver;
 mod runtime_error;
 mod scanner;
 mod stmt;
 mod token;
+mod value;
+mod vm;
 
 use lazy_static::lazy_static;
 use parser::Parser;
+use rustyline::{error::ReadlineError, DefaultEditor};
 use scanner::Scanner;
 use token::{Token, TokenKind};
+use vm::{InterpretResult, VM};
 
-use std::{
-    io::{BufRead, Write},
-    sync::RwLock,
-};
+use std::sync::RwLock;
+
+/// Where the REPL persists its line history between sessions.
+const HISTORY_PATH: &str = ".jlox_history";
 
 lazy_static! {
     static ref HAD_ERROR: RwLock<bool> = RwLock::new(false);
@@ -27,21 +39,61 @@ lazy_static! {
 }
 
 fn main() {
-    let args = std::env::args().collect::<Vec<_>>();
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    let bytecode = take_flag(&mut args, "--bytecode");
+    let emit = take_flag_value(&mut args, "--emit");
+    let load = take_flag_value(&mut args, "--load");
+
+    if let Some(path) = load {
+        run_compiled(std::path::Path::new(&path));
+        if *HAD_RUNTIME_ERROR.read().unwrap() {
+            std::process::exit(70);
+        }
+        return;
+    }
 
     match args.len() {
-        1 => run_prompt().unwrap(),
-        2 => run_file(&args[1]).unwrap(),
+        0 if emit.is_none() => run_prompt(bytecode).unwrap(),
+        1 => run_file(&args[0], bytecode, emit.as_deref()).unwrap(),
         _ => {
-            println!("Usage: jlox [script]");
+            println!("Usage: jlox [--bytecode] [--emit path.loxc] script");
+            println!("       jlox --load path.loxc");
             std::process::exit(64);
         }
     }
 }
 
-fn run_file(name: &str) -> Result<(), std::io::Error> {
+/// Removes and returns whether the boolean flag `name` was present in `args`.
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    match args.iter().position(|arg| arg == name) {
+        Some(index) => {
+            args.remove(index);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Removes and returns the value following the `name` flag in `args`, e.g.
+/// `--emit out.loxc` yields `Some("out.loxc")`.
+fn take_flag_value(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let index = args.iter().position(|arg| arg == name)?;
+    args.remove(index);
+    if index < args.len() {
+        Some(args.remove(index))
+    } else {
+        None
+    }
+}
+
+fn run_file(name: &str, bytecode: bool, emit: Option<&str>) -> Result<(), std::io::Error> {
     let source = std::fs::read_to_string(name)?;
-    run(&source);
+
+    if let Some(path) = emit {
+        run_bytecode_and_save(&source, std::path::Path::new(path));
+    } else {
+        run(&source, bytecode);
+    }
 
     if *HAD_ERROR.read().unwrap() {
         std::process::exit(65);
@@ -52,51 +104,139 @@ fn run_file(name: &str) -> Result<(), std::io::Error> {
     Ok(())
 }
 
-fn run_prompt() -> Result<(), std::io::Error> {
-    let stdin = std::io::stdin();
-    let mut reader = std::io::BufReader::new(stdin);
+/// Runs the interactive prompt on a readline-style line editor: arrow-key
+/// editing, persistent history across sessions, and Ctrl-C/Ctrl-D handling
+/// (Ctrl-C abandons the current line, Ctrl-D exits) via `rustyline` instead
+/// of the raw `BufReader` loop this used to be.
+fn run_prompt(bytecode: bool) -> eyre::Result<()> {
+    let mut editor = DefaultEditor::new()?;
+    let _ = editor.load_history(HISTORY_PATH);
+
     loop {
-        print!("> ");
-        std::io::stdout().flush()?;
-        let mut line = String::new();
-        if let Ok(0) = reader.read_line(&mut line) {
-            break;
+        match editor.readline("\x1b[1;32m>\x1b[0m ") {
+            Ok(line) => {
+                editor.add_history_entry(line.as_str())?;
+                run_and_print(&line, bytecode);
+                *HAD_ERROR.write().unwrap() = false;
+            }
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
         }
-        run(&line);
-        *HAD_ERROR.write().unwrap() = false;
     }
+
+    editor.save_history(HISTORY_PATH)?;
     Ok(())
 }
 
-fn run(source: &str) {
+/// Routes a line/file of source to either the tree-walk interpreter or the
+/// bytecode VM, depending on the `--bytecode` CLI flag. The two backends
+/// report errors through different mechanisms (`HAD_RUNTIME_ERROR` here vs.
+/// `InterpretResult` there), so this just maps the VM's result onto the same
+/// process-exit signals the tree-walk path already sets.
+fn run(source: &str, bytecode: bool) {
+    if bytecode {
+        run_bytecode(source);
+        return;
+    }
+
+    if let Some(statements) = parse_and_resolve(source) {
+        INTERPRETER.write().unwrap().interpret(&statements);
+    }
+}
+
+/// Like `run`, but when `source` parses down to a single bare expression
+/// statement, evaluates it and prints the resulting value instead of
+/// silently discarding it — the way most language REPLs echo results.
+/// With `--bytecode` this is the same as `run`: no value is echoed.
+fn run_and_print(source: &str, bytecode: bool) {
+    if bytecode {
+        run_bytecode(source);
+        return;
+    }
+
+    if let Some(statements) = parse_and_resolve(source) {
+        match statements.as_slice() {
+            [stmt::Stmt::Expression(expression)] => {
+                match INTERPRETER
+                    .write()
+                    .unwrap()
+                    .interpret_expr(&expression.expression)
+                {
+                    Ok(value) => println!("{}", value.read().unwrap()),
+                    Err(e) => crate::runtime_error(e),
+                }
+            }
+            statements => INTERPRETER.write().unwrap().interpret(statements),
+        }
+    }
+}
+
+/// Scans, parses, and resolves `source`, returning the resolved statements
+/// if all three phases succeeded, or `None` after reporting errors if any
+/// phase set `HAD_ERROR`.
+fn parse_and_resolve(source: &str) -> Option<Vec<stmt::Stmt>> {
     let mut scanner = Scanner::new(source);
-    let tokens = scanner.scan_tokens();
+    let (tokens, scan_errors) = scanner.scan_tokens();
+    for error in &scan_errors {
+        eprintln!("{}", error);
+        *HAD_ERROR.write().unwrap() = true;
+    }
+
     let mut parser = Parser::new(tokens);
     let statements = parser.parse();
 
     if *HAD_ERROR.read().unwrap() {
-        return;
+        return None;
     }
 
-    INTERPRETER
-        .write()
-        .unwrap()
-        .interpret(statements.as_ref().unwrap());
+    let statements = optimizer::optimize_statements(statements.unwrap());
+    resolver::Resolver::new().resolve(&statements);
+
+    if *HAD_ERROR.read().unwrap() {
+        return None;
+    }
+
+    Some(statements)
 }
 
-pub fn error(line: usize, message: &str) {
-    report(line, "", message);
+fn run_bytecode(source: &str) {
+    let mut vm = VM::new();
+    report_interpret_result(vm.interpret(source.as_bytes()));
+}
+
+/// Like `run_bytecode`, but also persists the compiled chunk to `path` as a
+/// `.loxc` artifact via `--emit`, so a later `--load` run can skip the
+/// scanner/compiler entirely.
+fn run_bytecode_and_save(source: &str, path: &std::path::Path) {
+    let mut vm = VM::new();
+    report_interpret_result(vm.interpret_and_save(source.as_bytes(), path));
+}
+
+/// Loads and runs a chunk previously written by `--emit`, bypassing the
+/// scanner and compiler entirely.
+fn run_compiled(path: &std::path::Path) {
+    let mut vm = VM::new();
+    report_interpret_result(vm.run_compiled(path));
+}
+
+fn report_interpret_result(result: eyre::Result<InterpretResult>) {
+    match result {
+        Ok(InterpretResult::Ok) => {}
+        Ok(InterpretResult::CompileError) => *HAD_ERROR.write().unwrap() = true,
+        Ok(InterpretResult::RuntimeError) => *HAD_RUNTIME_ERROR.write().unwrap() = true,
+        Err(e) => {
+            eprintln!("{}", e);
+            *HAD_RUNTIME_ERROR.write().unwrap() = true;
+        }
+    }
 }
 
-pub fn error_at_token(token: &Token, message: &str) {
+pub fn error_at_token(token: &Token, kind: &runtime_error::ErrorKind) {
     if token.kind == TokenKind::Eof {
-        report(token.line, " at end", message);
+        report(token.line, " at end", kind);
     } else {
-        report(
-            token.line,
-            &(String::from(" at '") + &token.lexeme + "'"),
-            message,
-        );
+        report(token.line, &format!(" at '{}'", token.lexeme), kind);
     }
 }
 
@@ -105,7 +245,7 @@ pub fn runtime_error(error: runtime_error::RuntimeError) {
     *HAD_RUNTIME_ERROR.write().unwrap() = true;
 }
 
-fn report(line: usize, whence: &str, message: &str) {
-    eprintln!("[line {}] Error{}: {}", line, whence, message);
+fn report(line: usize, whence: &str, kind: &runtime_error::ErrorKind) {
+    eprintln!("[line {}] Error{}: {}", line, whence, kind);
     *HAD_ERROR.write().unwrap() = true;
 }
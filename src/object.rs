@@ -2,7 +2,12 @@ use lazy_static::lazy_static;
 
 use std::{borrow::Cow, fmt::Debug, fmt::Display, sync::Arc, sync::RwLock};
 
-use crate::interpreter::Interpreter;
+use crate::{
+    environment::Environment,
+    interpreter::Interpreter,
+    runtime_error::{RuntimeError, Unwind},
+    stmt,
+};
 
 pub type LoxObject = Arc<RwLock<Object>>;
 
@@ -12,13 +17,13 @@ lazy_static! {
     static ref FALSE: LoxObject = Arc::new(RwLock::new(Object::Bool(false)));
 }
 
-#[derive(Debug)]
 pub enum Object {
     Nil,
     String(String),
     Number(f64),
     Bool(bool),
     BuiltinFunction(usize, fn(Vec<LoxObject>) -> LoxObject),
+    LoxFunction(stmt::Function, Arc<RwLock<Environment>>),
 }
 
 impl Object {
@@ -45,6 +50,13 @@ impl Object {
         Arc::new(RwLock::new(Object::BuiltinFunction(arity, func)))
     }
 
+    pub fn new_function(
+        declaration: stmt::Function,
+        closure: Arc<RwLock<Environment>>,
+    ) -> LoxObject {
+        Arc::new(RwLock::new(Object::LoxFunction(declaration, closure)))
+    }
+
     pub fn is_nil(&self) -> bool {
         match self {
             Object::Nil => true,
@@ -90,6 +102,7 @@ impl Object {
             Object::Number(n) => *n,
             Object::Bool(b) => *b as i32 as f64,
             Object::BuiltinFunction(..) => 0.0,
+            Object::LoxFunction(..) => 0.0,
         }
     }
 
@@ -108,12 +121,32 @@ impl Object {
             Object::Number(_) => false,
             Object::Bool(_) => false,
             Object::BuiltinFunction(..) => true,
+            Object::LoxFunction(..) => true,
         }
     }
 
-    pub fn call(&mut self, interpreter: &mut Interpreter, arguments: Vec<LoxObject>) -> LoxObject {
+    pub fn call(
+        &mut self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<LoxObject>,
+    ) -> Result<LoxObject, RuntimeError> {
         match self {
-            Object::BuiltinFunction(_, func) => func(arguments),
+            Object::BuiltinFunction(_, func) => Ok(func(arguments)),
+            Object::LoxFunction(declaration, closure) => {
+                let mut environment = Environment::new_enclosed(closure.clone());
+                for (param, arg) in declaration.params.iter().zip(arguments) {
+                    environment.define(&param.lexeme, arg);
+                }
+
+                match interpreter.execute_block(&declaration.body, environment) {
+                    Ok(()) => Ok(Self::nil()),
+                    Err(Unwind::Return(value)) => Ok(value),
+                    Err(Unwind::RuntimeError(e)) => Err(e),
+                    // The resolver rejects `break`/`continue` that are not
+                    // inside a loop of this same function body.
+                    Err(Unwind::Break) | Err(Unwind::Continue) => unreachable!(),
+                }
+            }
             _ => unreachable!(),
         }
     }
@@ -121,11 +154,27 @@ impl Object {
     pub fn arity(&self) -> usize {
         match self {
             Object::BuiltinFunction(arity, ..) => *arity,
+            Object::LoxFunction(declaration, ..) => declaration.params.len(),
             _ => std::usize::MAX,
         }
     }
 }
 
+impl Debug for Object {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Object::Nil => write!(f, "Nil"),
+            Object::String(s) => write!(f, "String({:?})", s),
+            Object::Number(n) => write!(f, "Number({:?})", n),
+            Object::Bool(b) => write!(f, "Bool({:?})", b),
+            Object::BuiltinFunction(..) => write!(f, "BuiltinFunction"),
+            Object::LoxFunction(declaration, ..) => {
+                write!(f, "LoxFunction({})", declaration.name.lexeme)
+            }
+        }
+    }
+}
+
 impl Display for Object {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -134,6 +183,7 @@ impl Display for Object {
             Object::Number(n) => write!(f, "{}", n),
             Object::Bool(b) => write!(f, "{}", b),
             Object::BuiltinFunction(..) => write!(f, "<native fn>"),
+            Object::LoxFunction(declaration, ..) => write!(f, "<fn {}>", declaration.name.lexeme),
         }
     }
 }
@@ -155,3 +205,40 @@ impl PartialEq for Object {
         }
     }
 }
+
+/// Heap-allocated value kind for the bytecode VM (`crate::vm`/`crate::compiler`),
+/// as distinct from the tree-walk `Object` above. Strings are stored as an
+/// `InternedStr` handle rather than owned text so `Value`'s `Equal` opcode and
+/// global-variable name lookups are integer comparisons.
+#[derive(Clone)]
+pub enum Obj {
+    String(crate::interner::InternedStr),
+}
+
+impl Display for Obj {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Obj::String(id) => write!(f, "{}", crate::interner::lookup(*id)),
+        }
+    }
+}
+
+// `InternedStr` is only a handle into this *process's* interner, and a
+// `.loxc` file can be loaded by a process whose interner never saw these
+// strings. So instead of (de)serializing the handle, we (de)serialize the
+// actual text and re-intern it on load, same as `Chunk::load` does for
+// identifier/literal constants compiled in a previous run.
+impl serde::Serialize for Obj {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Obj::String(id) => serializer.serialize_str(&crate::interner::lookup(*id)),
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Obj {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Ok(Obj::String(crate::interner::intern(&text)))
+    }
+}
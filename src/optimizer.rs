@@ -0,0 +1,239 @@
+use std::sync::Arc;
+
+use crate::{
+    expr::{self, Expr},
+    object::{LoxObject, Object},
+    stmt,
+    token::{Token, TokenKind},
+};
+
+/// Folds statically-known subtrees of the parsed program, e.g. `1 + 2` or
+/// `!true`, into a single `Expr::Literal` so the interpreter never has to
+/// redo that work at every evaluation.
+pub fn optimize_statements(statements: Vec<stmt::Stmt>) -> Vec<stmt::Stmt> {
+    statements.into_iter().map(optimize_stmt).collect()
+}
+
+fn optimize_stmt(stmt: stmt::Stmt) -> stmt::Stmt {
+    match stmt {
+        stmt::Stmt::Block(b) => stmt::Stmt::Block(stmt::Block {
+            statements: optimize_statements(b.statements),
+        }),
+        stmt::Stmt::Break(b) => stmt::Stmt::Break(b),
+        stmt::Stmt::Continue(c) => stmt::Stmt::Continue(c),
+        stmt::Stmt::Expression(e) => stmt::Stmt::Expression(stmt::Expression {
+            expression: optimize(e.expression),
+        }),
+        stmt::Stmt::Function(f) => {
+            // The optimizer runs once over the freshly-parsed AST, before the
+            // interpreter ever clones `body` into a closure, so this `Arc` is
+            // always uniquely held here. If that ever stops being true, skip
+            // optimizing the body rather than panicking.
+            let body = match Arc::try_unwrap(f.body) {
+                Ok(statements) => Arc::new(optimize_statements(statements)),
+                Err(shared) => shared,
+            };
+            stmt::Stmt::Function(stmt::Function {
+                name: f.name,
+                params: f.params,
+                body,
+            })
+        }
+        stmt::Stmt::If(i) => {
+            let condition = optimize(i.condition);
+            let then_branch = optimize_stmt(*i.then_branch);
+            let else_branch = i.else_branch.map(|e| optimize_stmt(*e));
+
+            match literal_value(&condition) {
+                Some(value) if value.read().unwrap().as_bool() => then_branch,
+                Some(_) => else_branch.unwrap_or_else(empty_block),
+                None => stmt::Stmt::If(stmt::If {
+                    condition,
+                    then_branch: Box::new(then_branch),
+                    else_branch: else_branch.map(Box::new),
+                }),
+            }
+        }
+        stmt::Stmt::Print(p) => stmt::Stmt::Print(stmt::Print {
+            expression: optimize(p.expression),
+        }),
+        stmt::Stmt::Return(r) => stmt::Stmt::Return(stmt::Return {
+            keyword: r.keyword,
+            value: r.value.map(optimize),
+        }),
+        stmt::Stmt::Var(v) => stmt::Stmt::Var(stmt::Var {
+            name: v.name,
+            initializer: v.initializer.map(optimize),
+        }),
+        stmt::Stmt::While(w) => {
+            let condition = optimize(w.condition);
+            let body = optimize_stmt(*w.body);
+            let increment = w.increment.map(optimize);
+
+            match literal_value(&condition) {
+                // The loop body (and increment) never run: the whole
+                // statement is dead.
+                Some(value) if !value.read().unwrap().as_bool() => empty_block(),
+                _ => stmt::Stmt::While(stmt::While {
+                    condition,
+                    body: Box::new(body),
+                    increment,
+                }),
+            }
+        }
+    }
+}
+
+/// A statically-dead branch collapses to this rather than disappearing
+/// outright, since `then_branch`/`body` etc. need a `Stmt` to hold, not an
+/// `Option`.
+fn empty_block() -> stmt::Stmt {
+    stmt::Stmt::Block(stmt::Block { statements: vec![] })
+}
+
+pub fn optimize(expr: Expr) -> Expr {
+    match expr {
+        Expr::Assign(a) => Expr::Assign(expr::Assign {
+            name: a.name,
+            value: Box::new(optimize(*a.value)),
+            depth: a.depth,
+        }),
+        Expr::Binary(b) => optimize_binary(b),
+        Expr::Call(c) => Expr::Call(expr::Call {
+            callee: Box::new(optimize(*c.callee)),
+            paren: c.paren,
+            arguments: c.arguments.into_iter().map(optimize).collect(),
+        }),
+        Expr::Grouping(g) => optimize_grouping(g),
+        Expr::Logical(l) => optimize_logical(l),
+        Expr::Unary(u) => optimize_unary(u),
+        literal @ (Expr::Literal(_) | Expr::Variable(_)) => literal,
+    }
+}
+
+fn literal_value(expr: &Expr) -> Option<LoxObject> {
+    match expr {
+        Expr::Literal(l) => Some(l.value.clone()),
+        _ => None,
+    }
+}
+
+fn optimize_binary(binary: expr::Binary) -> Expr {
+    let left = optimize(*binary.left);
+    let right = optimize(*binary.right);
+
+    if let (Some(left_value), Some(right_value)) = (literal_value(&left), literal_value(&right)) {
+        if let Some(folded) = fold_binary(&binary.operator, &left_value, &right_value) {
+            return Expr::Literal(expr::Literal { value: folded });
+        }
+    }
+
+    Expr::Binary(expr::Binary {
+        left: Box::new(left),
+        operator: binary.operator,
+        right: Box::new(right),
+    })
+}
+
+fn fold_binary(operator: &Token, left: &LoxObject, right: &LoxObject) -> Option<LoxObject> {
+    let l = left.read().unwrap();
+    let r = right.read().unwrap();
+
+    Some(match operator.kind {
+        TokenKind::Minus if l.is_number() && r.is_number() => {
+            Object::new_number(l.as_number() - r.as_number())
+        }
+        TokenKind::Slash if l.is_number() && r.is_number() => {
+            Object::new_number(l.as_number() / r.as_number())
+        }
+        TokenKind::Star if l.is_number() && r.is_number() => {
+            Object::new_number(l.as_number() * r.as_number())
+        }
+        TokenKind::Plus if l.is_number() && r.is_number() => {
+            Object::new_number(l.as_number() + r.as_number())
+        }
+        TokenKind::Plus if l.is_string() && r.is_string() => {
+            Object::new_string(l.to_string() + r.as_string().as_ref())
+        }
+        TokenKind::Greater if l.is_number() && r.is_number() => {
+            Object::new_bool(l.as_number() > r.as_number())
+        }
+        TokenKind::GreaterEqual if l.is_number() && r.is_number() => {
+            Object::new_bool(l.as_number() >= r.as_number())
+        }
+        TokenKind::Less if l.is_number() && r.is_number() => {
+            Object::new_bool(l.as_number() < r.as_number())
+        }
+        TokenKind::LessEqual if l.is_number() && r.is_number() => {
+            Object::new_bool(l.as_number() <= r.as_number())
+        }
+        TokenKind::EqualEqual => Object::new_bool(l.eq(&r)),
+        TokenKind::BangEqual => Object::new_bool(l.eq(&r)),
+        // Anything else (e.g. `1 + "a"`) is a runtime type error; leave the
+        // node alone so the interpreter reports it the usual way.
+        _ => return None,
+    })
+}
+
+fn optimize_logical(logical: expr::Logical) -> Expr {
+    let left = optimize(*logical.left);
+    let right = optimize(*logical.right);
+
+    // A constant left operand alone is enough to fold: `or` short-circuits
+    // on a truthy left, `and` short-circuits on a falsy left, without ever
+    // needing `right` (which may not be a literal at all). Otherwise the
+    // result is exactly whatever `right` evaluates to.
+    if let Some(left_value) = literal_value(&left) {
+        let short_circuits = left_value.read().unwrap().as_bool();
+        let collapses = match logical.operator.kind {
+            TokenKind::Or => short_circuits,
+            TokenKind::And => !short_circuits,
+            _ => unreachable!(),
+        };
+        return if collapses {
+            Expr::Literal(expr::Literal { value: left_value })
+        } else {
+            right
+        };
+    }
+
+    Expr::Logical(expr::Logical {
+        left: Box::new(left),
+        operator: logical.operator,
+        right: Box::new(right),
+    })
+}
+
+fn optimize_unary(unary: expr::Unary) -> Expr {
+    let right = optimize(*unary.right);
+
+    if let Some(right_value) = literal_value(&right) {
+        let r = right_value.read().unwrap();
+        let folded = match unary.operator.kind {
+            TokenKind::Bang => Some(Object::new_bool(!r.as_bool())),
+            TokenKind::Minus if r.is_number() => Some(Object::new_number(-r.as_number())),
+            _ => None,
+        };
+        drop(r);
+        if let Some(value) = folded {
+            return Expr::Literal(expr::Literal { value });
+        }
+    }
+
+    Expr::Unary(expr::Unary {
+        operator: unary.operator,
+        right: Box::new(right),
+    })
+}
+
+fn optimize_grouping(grouping: expr::Grouping) -> Expr {
+    let inner = optimize(*grouping.expression);
+
+    if literal_value(&inner).is_some() {
+        inner
+    } else {
+        Expr::Grouping(expr::Grouping {
+            expression: Box::new(inner),
+        })
+    }
+}
@@ -1,6 +1,9 @@
+use std::{cell::Cell, sync::Arc};
+
 use crate::{
     expr::Assign,
     expr::Binary,
+    expr::Call,
     expr::Expr,
     expr::Grouping,
     expr::Literal,
@@ -8,10 +11,15 @@ use crate::{
     expr::Unary,
     expr::Variable,
     object::Object,
+    runtime_error::ErrorKind,
     stmt::Block,
+    stmt::Break,
+    stmt::Continue,
     stmt::Expression,
+    stmt::Function,
     stmt::If,
     stmt::Print,
+    stmt::Return,
     stmt::Stmt,
     stmt::Var,
     stmt::While,
@@ -28,7 +36,7 @@ impl Parser {
         Self { tokens, current: 0 }
     }
 
-    pub fn parse(&mut self) -> Result<Vec<Stmt>, (Token, String)> {
+    pub fn parse(&mut self) -> Result<Vec<Stmt>, (Token, ErrorKind)> {
         let mut statements = vec![];
 
         while !self.at_end() {
@@ -41,7 +49,9 @@ impl Parser {
     }
 
     fn declaration(&mut self) -> Option<Stmt> {
-        let value = if self.matches(&[TokenKind::Var]) {
+        let value = if self.matches(&[TokenKind::Fun]) {
+            self.fun_declaration("function")
+        } else if self.matches(&[TokenKind::Var]) {
             self.var_declaration()
         } else {
             self.statement()
@@ -55,7 +65,48 @@ impl Parser {
         }
     }
 
-    fn var_declaration(&mut self) -> Result<Stmt, (Token, String)> {
+    fn fun_declaration(&mut self, kind: &str) -> Result<Stmt, (Token, ErrorKind)> {
+        let name = self
+            .consume(TokenKind::Identifier, &format!("Expect {} name.", kind))?
+            .clone();
+
+        self.consume(
+            TokenKind::LParen,
+            &format!("Expect '(' after {} name.", kind),
+        )?;
+        let mut params = vec![];
+        if !self.check(TokenKind::RParen) {
+            loop {
+                if params.len() >= 255 {
+                    Self::error(self.peek(), ErrorKind::TooMany("parameters"));
+                }
+
+                params.push(
+                    self.consume(TokenKind::Identifier, "Expect parameter name.")?
+                        .clone(),
+                );
+
+                if !self.matches(&[TokenKind::Comma]) {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenKind::RParen, "Expect ')' after parameters.")?;
+
+        self.consume(
+            TokenKind::LBrace,
+            &format!("Expect '{{' before {} body.", kind),
+        )?;
+        let body = self.block()?;
+
+        Ok(Stmt::Function(Function {
+            name,
+            params,
+            body: Arc::new(body),
+        }))
+    }
+
+    fn var_declaration(&mut self) -> Result<Stmt, (Token, ErrorKind)> {
         let name = self
             .consume(TokenKind::Identifier, "Expect variable name.")?
             .clone();
@@ -72,7 +123,13 @@ impl Parser {
         Ok(Stmt::Var(Var { name, initializer }))
     }
 
-    fn statement(&mut self) -> Result<Stmt, (Token, String)> {
+    fn statement(&mut self) -> Result<Stmt, (Token, ErrorKind)> {
+        if self.matches(&[TokenKind::Break]) {
+            return self.break_statement();
+        }
+        if self.matches(&[TokenKind::Continue]) {
+            return self.continue_statement();
+        }
         if self.matches(&[TokenKind::For]) {
             return self.for_statement();
         }
@@ -82,6 +139,9 @@ impl Parser {
         if self.matches(&[TokenKind::Print]) {
             return self.print_statement();
         }
+        if self.matches(&[TokenKind::Return]) {
+            return self.return_statement();
+        }
         if self.matches(&[TokenKind::While]) {
             return self.while_statement();
         }
@@ -94,7 +154,7 @@ impl Parser {
         self.expression_statement()
     }
 
-    fn for_statement(&mut self) -> Result<Stmt, (Token, String)> {
+    fn for_statement(&mut self) -> Result<Stmt, (Token, ErrorKind)> {
         self.consume(TokenKind::LParen, "Expect '(' after 'for'.")?;
         let initializer = if self.matches(&[TokenKind::Semicolon]) {
             None
@@ -115,18 +175,7 @@ impl Parser {
             Some(self.expression()?)
         };
         self.consume(TokenKind::RParen, "Expect ')' after for clauses.")?;
-        let mut body = self.statement()?;
-
-        if let Some(increment) = increment {
-            body = Stmt::Block(Block {
-                statements: vec![
-                    body,
-                    Stmt::Expression(Expression {
-                        expression: increment,
-                    }),
-                ],
-            });
-        }
+        let body = self.statement()?;
 
         let condition = condition.unwrap_or_else(|| {
             Expr::Literal(Literal {
@@ -134,21 +183,22 @@ impl Parser {
             })
         });
 
-        body = Stmt::While(While {
+        let mut stmt = Stmt::While(While {
             condition,
             body: Box::new(body),
+            increment,
         });
 
         if let Some(initializer) = initializer {
-            body = Stmt::Block(Block {
-                statements: vec![initializer, body],
+            stmt = Stmt::Block(Block {
+                statements: vec![initializer, stmt],
             });
         }
 
-        Ok(body)
+        Ok(stmt)
     }
 
-    fn if_statement(&mut self) -> Result<Stmt, (Token, String)> {
+    fn if_statement(&mut self) -> Result<Stmt, (Token, ErrorKind)> {
         self.consume(TokenKind::LParen, "Expect '(' after 'if'.")?;
         let condition = self.expression()?;
         self.consume(TokenKind::RParen, "Expect ')' after if condition.")?;
@@ -168,7 +218,7 @@ impl Parser {
         }))
     }
 
-    fn block(&mut self) -> Result<Vec<Stmt>, (Token, String)> {
+    fn block(&mut self) -> Result<Vec<Stmt>, (Token, ErrorKind)> {
         let mut statements = vec![];
 
         while !self.check(TokenKind::RBrace) && !self.at_end() {
@@ -181,33 +231,61 @@ impl Parser {
         Ok(statements)
     }
 
-    fn print_statement(&mut self) -> Result<Stmt, (Token, String)> {
+    fn print_statement(&mut self) -> Result<Stmt, (Token, ErrorKind)> {
         let value = self.expression()?;
         self.consume(TokenKind::Semicolon, "Expect ';' after value.")?;
         Ok(Stmt::Print(Print { expression: value }))
     }
 
-    fn while_statement(&mut self) -> Result<Stmt, (Token, String)> {
+    fn break_statement(&mut self) -> Result<Stmt, (Token, ErrorKind)> {
+        let keyword = self.previous().clone();
+        self.consume(TokenKind::Semicolon, "Expect ';' after 'break'.")?;
+        Ok(Stmt::Break(Break { keyword }))
+    }
+
+    fn continue_statement(&mut self) -> Result<Stmt, (Token, ErrorKind)> {
+        let keyword = self.previous().clone();
+        self.consume(TokenKind::Semicolon, "Expect ';' after 'continue'.")?;
+        Ok(Stmt::Continue(Continue { keyword }))
+    }
+
+    fn return_statement(&mut self) -> Result<Stmt, (Token, ErrorKind)> {
+        let keyword = self.previous().clone();
+        let value = if self.check(TokenKind::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+
+        self.consume(TokenKind::Semicolon, "Expect ';' after return value.")?;
+        Ok(Stmt::Return(Return { keyword, value }))
+    }
+
+    fn while_statement(&mut self) -> Result<Stmt, (Token, ErrorKind)> {
         self.consume(TokenKind::LParen, "Expect '(' after 'while'.")?;
         let condition = self.expression()?;
         self.consume(TokenKind::RParen, "Expect ')' after while condition")?;
 
         let body = Box::new(self.statement()?);
 
-        Ok(Stmt::While(While { condition, body }))
+        Ok(Stmt::While(While {
+            condition,
+            body,
+            increment: None,
+        }))
     }
 
-    fn expression_statement(&mut self) -> Result<Stmt, (Token, String)> {
+    fn expression_statement(&mut self) -> Result<Stmt, (Token, ErrorKind)> {
         let expr = self.expression()?;
         self.consume(TokenKind::Semicolon, "Expect ';' after expression.")?;
         Ok(Stmt::Expression(Expression { expression: expr }))
     }
 
-    fn expression(&mut self) -> Result<Expr, (Token, String)> {
+    fn expression(&mut self) -> Result<Expr, (Token, ErrorKind)> {
         self.assignment()
     }
 
-    fn assignment(&mut self) -> Result<Expr, (Token, String)> {
+    fn assignment(&mut self) -> Result<Expr, (Token, ErrorKind)> {
         let expr = self.or()?;
 
         if self.matches(&[TokenKind::Equal]) {
@@ -216,16 +294,20 @@ impl Parser {
 
             if let Expr::Variable(v) = &expr {
                 let name = v.name.clone();
-                return Ok(Expr::Assign(Assign { name, value }));
+                return Ok(Expr::Assign(Assign {
+                    name,
+                    value,
+                    depth: Cell::new(None),
+                }));
             }
 
-            Self::error(&equals, "Invalid assignment target.");
+            Self::error(&equals, ErrorKind::InvalidAssignmentTarget);
         }
 
         Ok(expr)
     }
 
-    fn or(&mut self) -> Result<Expr, (Token, String)> {
+    fn or(&mut self) -> Result<Expr, (Token, ErrorKind)> {
         let mut expr = self.and()?;
 
         while self.matches(&[TokenKind::Or]) {
@@ -241,7 +323,7 @@ impl Parser {
         Ok(expr)
     }
 
-    fn and(&mut self) -> Result<Expr, (Token, String)> {
+    fn and(&mut self) -> Result<Expr, (Token, ErrorKind)> {
         let mut expr = self.equality()?;
 
         while self.matches(&[TokenKind::And]) {
@@ -257,7 +339,7 @@ impl Parser {
         Ok(expr)
     }
 
-    fn equality(&mut self) -> Result<Expr, (Token, String)> {
+    fn equality(&mut self) -> Result<Expr, (Token, ErrorKind)> {
         let mut expr = self.comparison()?;
 
         while self.matches(&[TokenKind::BangEqual, TokenKind::EqualEqual]) {
@@ -273,7 +355,7 @@ impl Parser {
         Ok(expr)
     }
 
-    fn comparison(&mut self) -> Result<Expr, (Token, String)> {
+    fn comparison(&mut self) -> Result<Expr, (Token, ErrorKind)> {
         let mut expr = self.term()?;
 
         while self.matches(&[
@@ -294,7 +376,7 @@ impl Parser {
         Ok(expr)
     }
 
-    fn term(&mut self) -> Result<Expr, (Token, String)> {
+    fn term(&mut self) -> Result<Expr, (Token, ErrorKind)> {
         let mut expr = self.factor()?;
 
         while self.matches(&[TokenKind::Minus, TokenKind::Plus]) {
@@ -310,7 +392,7 @@ impl Parser {
         Ok(expr)
     }
 
-    fn factor(&mut self) -> Result<Expr, (Token, String)> {
+    fn factor(&mut self) -> Result<Expr, (Token, ErrorKind)> {
         let mut expr = self.unary()?;
 
         while self.matches(&[TokenKind::Slash, TokenKind::Star]) {
@@ -326,7 +408,7 @@ impl Parser {
         Ok(expr)
     }
 
-    fn unary(&mut self) -> Result<Expr, (Token, String)> {
+    fn unary(&mut self) -> Result<Expr, (Token, ErrorKind)> {
         if self.matches(&[TokenKind::Bang, TokenKind::Minus]) {
             let operator = self.previous().clone();
             let right = self.unary()?;
@@ -336,10 +418,47 @@ impl Parser {
             }));
         }
 
-        self.primary()
+        self.call()
+    }
+
+    fn call(&mut self) -> Result<Expr, (Token, ErrorKind)> {
+        let mut expr = self.primary()?;
+
+        while self.matches(&[TokenKind::LParen]) {
+            expr = self.finish_call(expr)?;
+        }
+
+        Ok(expr)
+    }
+
+    fn finish_call(&mut self, callee: Expr) -> Result<Expr, (Token, ErrorKind)> {
+        let mut arguments = vec![];
+        if !self.check(TokenKind::RParen) {
+            loop {
+                if arguments.len() >= 255 {
+                    Self::error(self.peek(), ErrorKind::TooMany("arguments"));
+                }
+
+                arguments.push(self.expression()?);
+
+                if !self.matches(&[TokenKind::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        let paren = self
+            .consume(TokenKind::RParen, "Expect ')' after arguments.")?
+            .clone();
+
+        Ok(Expr::Call(Call {
+            callee: Box::new(callee),
+            paren,
+            arguments,
+        }))
     }
 
-    fn primary(&mut self) -> Result<Expr, (Token, String)> {
+    fn primary(&mut self) -> Result<Expr, (Token, ErrorKind)> {
         if self.matches(&[TokenKind::False]) {
             return Ok(Expr::Literal(Literal {
                 value: Object::new_bool(false),
@@ -365,6 +484,7 @@ impl Parser {
         if self.matches(&[TokenKind::Identifier]) {
             return Ok(Expr::Variable(Variable {
                 name: self.previous().clone(),
+                depth: Cell::new(None),
             }));
         }
 
@@ -376,15 +496,21 @@ impl Parser {
             }));
         }
 
-        Err(Self::error(self.peek(), "Expect expression."))
+        Err(Self::error(
+            self.peek(),
+            ErrorKind::ExpectedToken("Expect expression.".to_string()),
+        ))
     }
 
-    fn consume(&mut self, kind: TokenKind, message: &str) -> Result<&Token, (Token, String)> {
+    fn consume(&mut self, kind: TokenKind, message: &str) -> Result<&Token, (Token, ErrorKind)> {
         if self.check(kind) {
             return Ok(self.advance());
         }
 
-        Err(Self::error(self.peek(), message))
+        Err(Self::error(
+            self.peek(),
+            ErrorKind::ExpectedToken(message.to_string()),
+        ))
     }
 
     fn synchronize(&mut self) {
@@ -403,7 +529,9 @@ impl Parser {
                 | TokenKind::If
                 | TokenKind::While
                 | TokenKind::Print
-                | TokenKind::Return => return,
+                | TokenKind::Return
+                | TokenKind::Break
+                | TokenKind::Continue => return,
                 _ => {}
             }
 
@@ -411,9 +539,9 @@ impl Parser {
         }
     }
 
-    fn error(token: &Token, message: &str) -> (Token, String) {
-        crate::error_at_token(token, message);
-        (token.clone(), message.to_string())
+    fn error(token: &Token, kind: ErrorKind) -> (Token, ErrorKind) {
+        crate::error_at_token(token, &kind);
+        (token.clone(), kind)
     }
 
     fn matches(&mut self, kinds: &[TokenKind]) -> bool {
@@ -0,0 +1,213 @@
+use std::{cell::Cell, collections::HashMap};
+
+use crate::{expr, runtime_error::ErrorKind, stmt, token::Token};
+
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionType {
+    None,
+    Function,
+}
+
+/// Walks the parsed `Vec<Stmt>` once, before interpretation, to resolve each
+/// variable reference to a lexical scope distance. This is what lets a
+/// closure keep referring to the variable it captured even if an outer scope
+/// later declares a new variable with the same name.
+///
+/// The resolved distance is stored directly on the `Variable`/`Assign` node
+/// (the `depth` field of `expr::Variable`/`expr::Assign`, a
+/// `Cell<Option<usize>>`) rather than in a side table keyed
+/// by an expression id, since every node already has a stable place to put
+/// it and reads it back without an extra lookup.
+pub struct Resolver {
+    scopes: Vec<HashMap<String, bool>>,
+    current_function: FunctionType,
+    loop_depth: usize,
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Self {
+            scopes: vec![],
+            current_function: FunctionType::None,
+            loop_depth: 0,
+        }
+    }
+
+    pub fn resolve(&mut self, statements: &[stmt::Stmt]) {
+        for statement in statements {
+            self.resolve_stmt(statement);
+        }
+    }
+
+    fn resolve_stmt(&mut self, stmt: &stmt::Stmt) {
+        stmt.accept(self)
+    }
+
+    fn resolve_expr(&mut self, expr: &expr::Expr) {
+        expr.accept(self)
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), false);
+        }
+    }
+
+    fn define(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), true);
+        }
+    }
+
+    fn resolve_local(&self, name: &Token, depth: &Cell<Option<usize>>) {
+        for (i, scope) in self.scopes.iter().rev().enumerate() {
+            if scope.contains_key(&name.lexeme) {
+                depth.set(Some(i));
+                return;
+            }
+        }
+        // Not found in any scope: leave it as a global lookup.
+    }
+
+    fn resolve_function(&mut self, function: &stmt::Function, kind: FunctionType) {
+        let enclosing_function = self.current_function;
+        self.current_function = kind;
+        // A `break`/`continue` can't reach through a function boundary to a
+        // loop in the enclosing scope, so start the nested function fresh.
+        let enclosing_loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+
+        self.begin_scope();
+        for param in &function.params {
+            self.declare(param);
+            self.define(param);
+        }
+        self.resolve(&function.body);
+        self.end_scope();
+
+        self.current_function = enclosing_function;
+        self.loop_depth = enclosing_loop_depth;
+    }
+}
+
+impl stmt::Visitor<()> for Resolver {
+    fn visit_block_stmt(&mut self, stmt: &stmt::Block) {
+        self.begin_scope();
+        self.resolve(&stmt.statements);
+        self.end_scope();
+    }
+
+    fn visit_break_stmt(&mut self, stmt: &stmt::Break) {
+        if self.loop_depth == 0 {
+            crate::error_at_token(&stmt.keyword, &ErrorKind::BreakOutsideLoop);
+        }
+    }
+
+    fn visit_continue_stmt(&mut self, stmt: &stmt::Continue) {
+        if self.loop_depth == 0 {
+            crate::error_at_token(&stmt.keyword, &ErrorKind::ContinueOutsideLoop);
+        }
+    }
+
+    fn visit_expression_stmt(&mut self, stmt: &stmt::Expression) {
+        self.resolve_expr(&stmt.expression);
+    }
+
+    fn visit_function_stmt(&mut self, stmt: &stmt::Function) {
+        self.declare(&stmt.name);
+        self.define(&stmt.name);
+
+        self.resolve_function(stmt, FunctionType::Function);
+    }
+
+    fn visit_if_stmt(&mut self, stmt: &stmt::If) {
+        self.resolve_expr(&stmt.condition);
+        self.resolve_stmt(&stmt.then_branch);
+        if let Some(else_branch) = &stmt.else_branch {
+            self.resolve_stmt(else_branch);
+        }
+    }
+
+    fn visit_print_stmt(&mut self, stmt: &stmt::Print) {
+        self.resolve_expr(&stmt.expression);
+    }
+
+    fn visit_return_stmt(&mut self, stmt: &stmt::Return) {
+        if self.current_function == FunctionType::None {
+            crate::error_at_token(&stmt.keyword, &ErrorKind::ReturnOutsideFunction);
+        }
+
+        if let Some(value) = &stmt.value {
+            self.resolve_expr(value);
+        }
+    }
+
+    fn visit_var_stmt(&mut self, stmt: &stmt::Var) {
+        self.declare(&stmt.name);
+        if let Some(initializer) = &stmt.initializer {
+            self.resolve_expr(initializer);
+        }
+        self.define(&stmt.name);
+    }
+
+    fn visit_while_stmt(&mut self, stmt: &stmt::While) {
+        self.resolve_expr(&stmt.condition);
+        self.loop_depth += 1;
+        self.resolve_stmt(&stmt.body);
+        if let Some(increment) = &stmt.increment {
+            self.resolve_expr(increment);
+        }
+        self.loop_depth -= 1;
+    }
+}
+
+impl expr::Visitor<()> for Resolver {
+    fn visit_assign_expr(&mut self, expr: &expr::Assign) {
+        self.resolve_expr(&expr.value);
+        self.resolve_local(&expr.name, &expr.depth);
+    }
+
+    fn visit_binary_expr(&mut self, expr: &expr::Binary) {
+        self.resolve_expr(&expr.left);
+        self.resolve_expr(&expr.right);
+    }
+
+    fn visit_call_expr(&mut self, expr: &expr::Call) {
+        self.resolve_expr(&expr.callee);
+        for argument in &expr.arguments {
+            self.resolve_expr(argument);
+        }
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &expr::Grouping) {
+        self.resolve_expr(&expr.expression);
+    }
+
+    fn visit_literal_expr(&mut self, _expr: &expr::Literal) {}
+
+    fn visit_logical_expr(&mut self, expr: &expr::Logical) {
+        self.resolve_expr(&expr.left);
+        self.resolve_expr(&expr.right);
+    }
+
+    fn visit_unary_expr(&mut self, expr: &expr::Unary) {
+        self.resolve_expr(&expr.right);
+    }
+
+    fn visit_variable_expr(&mut self, expr: &expr::Variable) {
+        if let Some(scope) = self.scopes.last() {
+            if scope.get(&expr.name.lexeme) == Some(&false) {
+                crate::error_at_token(&expr.name, &ErrorKind::ReadLocalInOwnInitializer);
+            }
+        }
+
+        self.resolve_local(&expr.name, &expr.depth);
+    }
+}
@@ -1,16 +1,80 @@
 use std::{error::Error, fmt::Display};
 
-use crate::token::Token;
+use crate::{object::LoxObject, token::Token};
 
+/// Every failure category the scanner, parser, resolver, and interpreter can
+/// produce. Matching on a variant (rather than scraping the rendered string)
+/// is what lets callers distinguish, say, an undefined variable from a type
+/// error instead of only knowing "something went wrong".
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    UnexpectedChar(char),
+    UnterminatedString,
+    /// A `consume`/primary-expression failure in the parser; holds the full
+    /// "Expect ..." message since each call site already names exactly what
+    /// it wanted (some of those messages are built with `format!`, hence an
+    /// owned `String` rather than `&'static str`).
+    ExpectedToken(String),
+    InvalidAssignmentTarget,
+    /// The parser's 255-parameter/argument limit; holds "parameters" or
+    /// "arguments".
+    TooMany(&'static str),
+    UndefinedVariable(String),
+    TypeError(String),
+    ArityMismatch { expected: usize, got: usize },
+    BreakOutsideLoop,
+    ContinueOutsideLoop,
+    ReturnOutsideFunction,
+    ReadLocalInOwnInitializer,
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedChar(c) => write!(f, "Unexpected character '{}'.", c),
+            Self::UnterminatedString => write!(f, "Unterminated string."),
+            Self::ExpectedToken(message) => write!(f, "{}", message),
+            Self::InvalidAssignmentTarget => write!(f, "Invalid assignment target."),
+            Self::TooMany(noun) => write!(f, "Can't have more than 255 {}.", noun),
+            Self::UndefinedVariable(name) => write!(f, "Undefined variable '{}'.", name),
+            Self::TypeError(message) => write!(f, "{}", message),
+            Self::ArityMismatch { expected, got } => {
+                write!(f, "Expected {} arguments but got {}.", expected, got)
+            }
+            Self::BreakOutsideLoop => write!(f, "Can't use 'break' outside of a loop."),
+            Self::ContinueOutsideLoop => write!(f, "Can't use 'continue' outside of a loop."),
+            Self::ReturnOutsideFunction => write!(f, "Can't return from top-level code."),
+            Self::ReadLocalInOwnInitializer => {
+                write!(f, "Can't read local variable in its own initializer.")
+            }
+        }
+    }
+}
+
+/// A scan-time failure, reported before any token exists to hang an error on.
+#[derive(Debug, Clone)]
+pub struct ScanError {
+    pub line: usize,
+    pub kind: ErrorKind,
+}
+
+impl Display for ScanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[line {}] Error: {}", self.line, self.kind)
+    }
+}
+
+/// An honest runtime failure: a bad operand type, an undefined variable, a
+/// non-callable callee, etc.
 #[derive(Debug)]
 pub struct RuntimeError {
-    token: Token,
-    message: String,
+    pub token: Token,
+    pub kind: ErrorKind,
 }
 
 impl RuntimeError {
-    pub fn new(token: Token, message: String) -> Self {
-        Self { token, message }
+    pub fn new(token: Token, kind: ErrorKind) -> Self {
+        Self { token, kind }
     }
 }
 
@@ -19,9 +83,26 @@ impl Display for RuntimeError {
         write!(
             f,
             "[line {}] Error at '{}': {}",
-            self.token.line, self.token.lexeme, self.message
+            self.token.line, self.token.lexeme, self.kind
         )
     }
 }
 
 impl Error for RuntimeError {}
+
+/// Non-local control flow that unwinds through `execute`/`execute_block`,
+/// kept distinct from `RuntimeError` so a `return` statement can propagate
+/// up to the nearest `Object::call` without being reported as a failure.
+#[derive(Debug)]
+pub enum Unwind {
+    Return(LoxObject),
+    Break,
+    Continue,
+    RuntimeError(RuntimeError),
+}
+
+impl From<RuntimeError> for Unwind {
+    fn from(e: RuntimeError) -> Self {
+        Self::RuntimeError(e)
+    }
+}
@@ -5,6 +5,7 @@ use std::collections::HashMap;
 use crate::{
     object::LoxObject,
     object::Object,
+    runtime_error::{ErrorKind, ScanError},
     token::{Token, TokenKind},
 };
 
@@ -13,7 +14,9 @@ lazy_static! {
         let mut keywords = HashMap::new();
 
         keywords.insert(String::from("and"), TokenKind::And);
+        keywords.insert(String::from("break"), TokenKind::Break);
         keywords.insert(String::from("class"), TokenKind::Class);
+        keywords.insert(String::from("continue"), TokenKind::Continue);
         keywords.insert(String::from("else"), TokenKind::Else);
         keywords.insert(String::from("false"), TokenKind::False);
         keywords.insert(String::from("for"), TokenKind::For);
@@ -36,6 +39,7 @@ lazy_static! {
 pub struct Scanner {
     source: Vec<char>,
     tokens: Vec<Token>,
+    errors: Vec<ScanError>,
 
     start: usize,
     current: usize,
@@ -47,6 +51,7 @@ impl Scanner {
         Self {
             source: source.chars().collect(),
             tokens: vec![],
+            errors: vec![],
 
             start: 0,
             current: 0,
@@ -54,7 +59,10 @@ impl Scanner {
         }
     }
 
-    pub fn scan_tokens(&mut self) -> Vec<Token> {
+    /// Scans the whole source, accumulating an `ErrorKind` per bad character
+    /// or unterminated string instead of reporting through the global error
+    /// flag on the spot, so a single call surfaces every scan error at once.
+    pub fn scan_tokens(&mut self) -> (Vec<Token>, Vec<ScanError>) {
         while !self.at_end() {
             self.start = self.current;
             self.scan_token();
@@ -67,7 +75,14 @@ impl Scanner {
             self.line,
         ));
 
-        self.tokens.clone()
+        (self.tokens.clone(), self.errors.clone())
+    }
+
+    fn error(&mut self, kind: ErrorKind) {
+        self.errors.push(ScanError {
+            line: self.line,
+            kind,
+        });
     }
 
     fn scan_token(&mut self) {
@@ -129,7 +144,7 @@ impl Scanner {
             '"' => self.string(),
             c if c.is_digit(10) => self.number(),
             c if c.is_alphabetic() || c == '_' => self.identifier(),
-            _ => crate::error(self.line, "Unexpected character."),
+            c => self.error(ErrorKind::UnexpectedChar(c)),
         }
     }
 
@@ -178,7 +193,7 @@ impl Scanner {
         }
 
         if self.at_end() {
-            crate::error(self.line, "Unterminated string.");
+            self.error(ErrorKind::UnterminatedString);
             return;
         }
 
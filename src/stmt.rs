@@ -1,29 +1,46 @@
+use std::sync::Arc;
+
 use crate::{expr::Expr, token::Token};
 
 pub trait Visitor<T> {
     fn visit_block_stmt(&mut self, stmt: &Block) -> T;
+    fn visit_break_stmt(&mut self, stmt: &Break) -> T;
+    fn visit_continue_stmt(&mut self, stmt: &Continue) -> T;
     fn visit_expression_stmt(&mut self, stmt: &Expression) -> T;
+    fn visit_function_stmt(&mut self, stmt: &Function) -> T;
     fn visit_if_stmt(&mut self, stmt: &If) -> T;
     fn visit_print_stmt(&mut self, stmt: &Print) -> T;
+    fn visit_return_stmt(&mut self, stmt: &Return) -> T;
     fn visit_var_stmt(&mut self, stmt: &Var) -> T;
+    fn visit_while_stmt(&mut self, stmt: &While) -> T;
 }
 
 pub enum Stmt {
     Block(Block),
+    Break(Break),
+    Continue(Continue),
     Expression(Expression),
+    Function(Function),
     If(If),
     Print(Print),
+    Return(Return),
     Var(Var),
+    While(While),
 }
 
 impl Stmt {
     pub fn accept<T>(&self, visitor: &mut impl Visitor<T>) -> T {
         match self {
             Stmt::Block(b) => visitor.visit_block_stmt(b),
+            Stmt::Break(b) => visitor.visit_break_stmt(b),
+            Stmt::Continue(c) => visitor.visit_continue_stmt(c),
             Stmt::Expression(e) => visitor.visit_expression_stmt(e),
+            Stmt::Function(f) => visitor.visit_function_stmt(f),
             Stmt::If(i) => visitor.visit_if_stmt(i),
             Stmt::Print(p) => visitor.visit_print_stmt(p),
+            Stmt::Return(r) => visitor.visit_return_stmt(r),
             Stmt::Var(v) => visitor.visit_var_stmt(v),
+            Stmt::While(w) => visitor.visit_while_stmt(w),
         }
     }
 }
@@ -32,10 +49,34 @@ pub struct Block {
     pub statements: Vec<Stmt>,
 }
 
+pub struct Break {
+    pub keyword: Token,
+}
+
+pub struct Continue {
+    pub keyword: Token,
+}
+
 pub struct Expression {
     pub expression: Expr,
 }
 
+#[derive(Clone)]
+pub struct Function {
+    pub name: Token,
+    pub params: Vec<Token>,
+    // Shared rather than owned so `Object::new_function` can cheaply clone the
+    // declaration into every closure created over it. `Arc` (not `Rc`)
+    // because `Object`/`Interpreter` need to be `Send`: `Rc` isn't `Send`,
+    // and `RwLock<T>` (used throughout as `Arc<RwLock<Object>>`,
+    // `Arc<RwLock<Environment>>`, and the `lazy_static! INTERPRETER`) is only
+    // `Send + Sync` itself when `T: Send`. `Object` still isn't `Sync` — the
+    // resolver's `Cell<Option<usize>>` on `Expr::Variable`/`Assign` sees to
+    // that — but `RwLock` doesn't require `T: Sync` to provide it, so that's
+    // not a problem here.
+    pub body: Arc<Vec<Stmt>>,
+}
+
 pub struct If {
     pub condition: Expr,
     pub then_branch: Box<Stmt>,
@@ -46,7 +87,21 @@ pub struct Print {
     pub expression: Expr,
 }
 
+pub struct Return {
+    pub keyword: Token,
+    pub value: Option<Expr>,
+}
+
 pub struct Var {
     pub name: Token,
     pub initializer: Option<Expr>,
 }
+
+pub struct While {
+    pub condition: Expr,
+    pub body: Box<Stmt>,
+    /// Set only when this loop is the desugared form of a C-style `for`, so
+    /// `continue` inside `body` still runs the increment before the next
+    /// condition check instead of skipping it.
+    pub increment: Option<Expr>,
+}
@@ -30,7 +30,9 @@ pub enum TokenKind {
     Number,
 
     And,
+    Break,
     Class,
+    Continue,
     Else,
     False,
     For,
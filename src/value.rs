@@ -5,7 +5,7 @@ use std::{
 
 use crate::object::Obj;
 
-#[derive(Clone)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub enum Value {
     Bool(bool),
     Nil,
@@ -46,6 +46,16 @@ impl Value {
         }
     }
 
+    pub fn is_string(&self) -> bool {
+        match self {
+            Self::Obj(o) => {
+                let Obj::String(_) = o.as_ref();
+                true
+            }
+            _ => false,
+        }
+    }
+
     pub fn as_bool(&self) -> bool {
         if let Self::Bool(b) = self {
             *b
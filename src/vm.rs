@@ -1,10 +1,11 @@
-use std::convert::TryFrom;
+use std::{collections::HashMap, convert::TryFrom};
 
 use crate::{
-    chunk::{Chunk, OpCode},
+    chunk::{Chunk, ChunkError, OpCode},
     common::DEBUG_TRACE_EXECUTION,
     compiler::compile,
     debug::disassemble_instruction,
+    interner::{self, InternedStr},
     object::Obj,
     value::Value,
 };
@@ -14,6 +15,7 @@ pub struct VM {
     pub chunk: Option<Box<Chunk>>,
     ip: usize,
     stack: Vec<Value>,
+    globals: HashMap<InternedStr, Value>,
 }
 
 pub enum InterpretResult {
@@ -26,7 +28,7 @@ macro_rules! common_op {
     ($vm:ident, $op:tt) => {{
         if !$vm.stack.last().unwrap().is_number() || !$vm.stack[$vm.stack.len() - 2].is_number() {
             $vm.runtime_error("Operands must be numbers.");
-            return InterpretResult::RuntimeError;
+            return Ok(InterpretResult::RuntimeError);
         }
         let b = $vm.stack.pop().unwrap();
         let a = $vm.stack.pop().unwrap();
@@ -66,27 +68,91 @@ impl VM {
         Ok(self.run())
     }
 
-    fn read_byte(&mut self) -> u8 {
-        let byte = self.chunk.as_ref().unwrap().code[self.ip];
+    /// Compiles `source`, saves the resulting chunk to `path` as a `.loxc`
+    /// artifact, and runs it, so a later `run_compiled` can skip recompiling.
+    pub fn interpret_and_save(
+        &mut self,
+        source: &[u8],
+        path: &std::path::Path,
+    ) -> eyre::Result<InterpretResult> {
+        let mut chunk = Chunk::new();
+        if !compile(source, &mut chunk)? {
+            return Ok(InterpretResult::CompileError);
+        }
+        chunk.save(path)?;
+
+        self.chunk = Some(Box::new(chunk));
+        self.ip = 0;
+
+        Ok(self.run())
+    }
+
+    /// Loads a previously compiled chunk from `path` and executes it directly,
+    /// bypassing the scanner and compiler entirely.
+    pub fn run_compiled(&mut self, path: &std::path::Path) -> eyre::Result<InterpretResult> {
+        let chunk = Chunk::load(path)?;
+
+        self.chunk = Some(Box::new(chunk));
+        self.ip = 0;
+
+        Ok(self.run())
+    }
+
+    fn read_byte(&mut self) -> Result<u8, ChunkError> {
+        let byte = self.chunk.as_ref().unwrap().read(self.ip)?;
         self.ip += 1;
-        byte
+        Ok(byte)
+    }
+
+    fn read_constant(&mut self) -> Result<Value, ChunkError> {
+        let offset = self.read_byte()? as usize;
+        self.chunk
+            .as_ref()
+            .unwrap()
+            .read_constant(offset)
+            .cloned()
+    }
+
+    fn read_short(&mut self) -> Result<u16, ChunkError> {
+        let hi = self.read_byte()?;
+        let lo = self.read_byte()?;
+        Ok(u16::from_be_bytes([hi, lo]))
     }
 
-    fn read_constant(&mut self) -> Value {
-        let offset = self.read_byte() as usize;
-        self.chunk.as_ref().unwrap().constants[offset].clone()
+    fn read_string(&mut self) -> Result<InternedStr, ChunkError> {
+        let idx = self.read_byte()? as usize;
+        match self.chunk.as_ref().unwrap().read_constant(idx)?.clone() {
+            Value::Obj(obj) => {
+                let Obj::String(id) = *obj;
+                Ok(id)
+            }
+            _ => Err(ChunkError::ConstantNotAString(idx)),
+        }
     }
 
     fn concatenate(&mut self) {
-        let mut b = self.stack.pop().unwrap().into_obj().into_string();
-        let mut a = self.stack.pop().unwrap().into_obj().into_string();
-        let mut result = vec![];
-        result.append(&mut a);
-        result.append(&mut b);
-        self.stack.push(Value::Obj(Box::new(Obj::String(result))));
+        let b = self.stack.pop().unwrap();
+        let a = self.stack.pop().unwrap();
+        let id = crate::interner::intern(&format!("{}{}", a, b));
+        self.stack.push(Value::Obj(Box::new(Obj::String(id))));
     }
 
+    /// Runs the chunk until it returns, or a stack/chunk error aborts it; a
+    /// `ChunkError` here always means a truncated or corrupt chunk (the
+    /// compiler never emits an out-of-bounds operand), so `run` reports it
+    /// the same way as any other runtime error rather than propagating it
+    /// as a compiler bug.
     fn run(&mut self) -> InterpretResult {
+        match self.run_fallible() {
+            Ok(result) => result,
+            Err(e) => {
+                self.runtime_error(&e.to_string());
+                InterpretResult::RuntimeError
+            }
+        }
+    }
+
+    fn run_fallible(&mut self) -> Result<InterpretResult, ChunkError> {
         loop {
             if DEBUG_TRACE_EXECUTION {
                 print!("          ");
@@ -96,15 +162,58 @@ impl VM {
                 println!();
                 disassemble_instruction(self.chunk.as_ref().unwrap(), self.ip);
             }
-            if let Ok(oc) = OpCode::try_from(self.read_byte()) {
+            if let Ok(oc) = OpCode::try_from(self.read_byte()?) {
                 match oc {
                     OpCode::Constant => {
-                        let constant = self.read_constant();
+                        let constant = self.read_constant()?;
                         self.stack.push(constant);
                     }
                     OpCode::Nil => self.stack.push(Value::Nil),
                     OpCode::True => self.stack.push(Value::Bool(true)),
                     OpCode::False => self.stack.push(Value::Bool(false)),
+                    OpCode::Pop => {
+                        self.stack.pop();
+                    }
+                    OpCode::GetLocal => {
+                        let slot = self.read_byte()? as usize;
+                        self.stack.push(self.stack[slot].clone());
+                    }
+                    OpCode::SetLocal => {
+                        let slot = self.read_byte()? as usize;
+                        self.stack[slot] = self.stack.last().unwrap().clone();
+                    }
+                    OpCode::GetGlobal => {
+                        let name = self.read_string()?;
+                        match self.globals.get(&name) {
+                            Some(value) => self.stack.push(value.clone()),
+                            None => {
+                                let message =
+                                    format!("Undefined variable '{}'.", interner::lookup(name));
+                                self.runtime_error(&message);
+                                return Ok(InterpretResult::RuntimeError);
+                            }
+                        }
+                    }
+                    OpCode::DefineGlobal => {
+                        let name = self.read_string()?;
+                        let value = self.stack.pop().unwrap();
+                        self.globals.insert(name, value);
+                    }
+                    OpCode::SetGlobal => {
+                        let name = self.read_string()?;
+                        if !self.globals.contains_key(&name) {
+                            let message =
+                                format!("Undefined variable '{}'.", interner::lookup(name));
+                            self.runtime_error(&message);
+                            return Ok(InterpretResult::RuntimeError);
+                        }
+                        self.globals
+                            .insert(name, self.stack.last().unwrap().clone());
+                    }
+                    OpCode::Print => {
+                        let value = self.stack.pop().unwrap();
+                        println!("{}", value);
+                    }
                     OpCode::Equal => {
                         let b = self.stack.pop().unwrap();
                         let a = self.stack.pop().unwrap();
@@ -125,7 +234,7 @@ impl VM {
                             self.stack.push(Value::Number(a + b));
                         } else {
                             self.runtime_error("Operands must be two numbers or two strings.");
-                            return InterpretResult::RuntimeError;
+                            return Ok(InterpretResult::RuntimeError);
                         }
                     }
                     OpCode::Sub => binary_op!(self, -),
@@ -142,14 +251,28 @@ impl VM {
                             self.stack.push(-val);
                         } else {
                             self.runtime_error("Operand must be a number.");
-                            return InterpretResult::RuntimeError;
+                            return Ok(InterpretResult::RuntimeError);
                         }
                     }
+                    OpCode::Jump => {
+                        let offset = self.read_short()?;
+                        self.ip += offset as usize;
+                    }
+                    OpCode::JumpIfFalse => {
+                        let offset = self.read_short()?;
+                        if self.stack.last().unwrap().is_falsey() {
+                            self.ip += offset as usize;
+                        }
+                    }
+                    OpCode::Loop => {
+                        let offset = self.read_short()?;
+                        self.ip -= offset as usize;
+                    }
                     OpCode::Return => {
                         if let Some(top) = self.stack.pop() {
                             println!("{}", top);
                         }
-                        return InterpretResult::Ok;
+                        return Ok(InterpretResult::Ok);
                     }
                 }
             }
@@ -160,7 +283,66 @@ impl VM {
         eprintln!("{}", message);
 
         let instruction = self.ip - 1;
-        let line = self.chunk.as_ref().unwrap().lines[instruction];
+        let line = self.chunk.as_ref().unwrap().get_line(instruction);
         eprintln!("[line {}] in script", line);
     }
+
+    #[cfg(test)]
+    fn global(&self, name: &str) -> Option<&Value> {
+        self.globals.get(&interner::intern(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(source: &str) -> VM {
+        let mut vm = VM::new();
+        assert!(matches!(
+            vm.interpret(source.as_bytes()).unwrap(),
+            InterpretResult::Ok
+        ));
+        vm
+    }
+
+    #[test]
+    fn and_short_circuits_without_evaluating_the_right_side() {
+        let vm = run("var ran = false; false and (ran = true); print ran;");
+        assert!(!vm.global("ran").unwrap().as_bool());
+    }
+
+    #[test]
+    fn or_short_circuits_without_evaluating_the_right_side() {
+        let vm = run("var ran = false; true or (ran = true); print ran;");
+        assert!(!vm.global("ran").unwrap().as_bool());
+    }
+
+    #[test]
+    fn string_constants_round_trip_through_save_and_load() {
+        // The decoys shift which handles newly interned strings receive
+        // around the save. The interner is shared process-wide and never
+        // reassigns a handle, so within this one process "hi" keeps its
+        // handle either way: this checks that a string constant comes back
+        // from save and load as the same text, not the cross-process case.
+        interner::intern("decoy-before-save");
+
+        let path = std::env::temp_dir().join(format!(
+            "rustlox-test-roundtrip-{}-{}.loxc",
+            std::process::id(),
+            line!()
+        ));
+
+        let mut vm = VM::new();
+        vm.interpret_and_save(b"var s = \"hi\";", &path).unwrap();
+
+        interner::intern("decoy-after-save");
+
+        let mut vm = VM::new();
+        let result = vm.run_compiled(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert!(matches!(result, InterpretResult::Ok));
+        assert_eq!(vm.global("s").unwrap().to_string(), "hi");
+    }
 }